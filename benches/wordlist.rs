@@ -0,0 +1,70 @@
+//! Wordlist throughput for a fast hash (MD5): the original `word_comp` path
+//! (`BufRead::lines`, a `String` per line, lowercase hex compare) against the
+//! cracker as shipped, reading a memory-mapped source with borrowed lines
+//! and comparing raw digests.
+//!
+//! Run with `cargo bench --bench wordlist`.
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    time::{Duration, Instant},
+};
+use hash_forge::wordlist::Source;
+use hash_forge::{create_verifier, Cracker, HashMode, Outcome, Position};
+
+const WORDS: usize = 2_000_000;
+
+fn hash_hex(input: &[u8]) -> String {
+    md5::compute(input).0.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn lines_hex(path: &std::path::Path, target: &str) -> usize {
+    let reader = BufReader::new(fs::File::open(path).unwrap());
+    let mut matches = 0;
+    for word in reader.lines().map_while(Result::ok) {
+        if hash_hex(word.trim().as_bytes()).eq_ignore_ascii_case(target) {
+            matches += 1;
+        }
+    }
+    matches
+}
+
+/// Candidates the cracker tried before cracking `target`.
+fn cracker(path: &std::path::Path, target: &str) -> u64 {
+    let verifier = create_verifier(&HashMode::MD5, target, None).unwrap();
+    let sources = [Source::File(path.to_path_buf())];
+    let report = Cracker::new(vec![verifier.as_ref()]).run(&sources, &Position::default(), &mut ());
+    assert_eq!(report.outcome, Outcome::Cracked);
+    report.tried
+}
+
+fn report(name: &str, elapsed: Duration) -> f64 {
+    let rate = WORDS as f64 / elapsed.as_secs_f64();
+    println!("{:<28} {:>8.1} ms  {:>12.0} words/s", name, elapsed.as_secs_f64() * 1e3, rate);
+    rate
+}
+
+fn main() {
+    let path = std::env::temp_dir().join(format!("hash_forge_bench_{}.txt", std::process::id()));
+    let mut out = std::io::BufWriter::new(fs::File::create(&path).unwrap());
+    for i in 0..WORDS {
+        writeln!(out, "password{}", i).unwrap();
+    }
+    drop(out);
+
+    // Last word in the list, so both paths do the full scan
+    let target = md5::compute(format!("password{}", WORDS - 1)).0;
+    let target_hex = hex::encode(target);
+
+    let start = Instant::now();
+    assert_eq!(lines_hex(&path, &target_hex), 1);
+    let before = report("lines() + hex compare", start.elapsed());
+
+    let start = Instant::now();
+    assert_eq!(cracker(&path, &target_hex), WORDS as u64);
+    let after = report("Cracker (mmap, raw digest)", start.elapsed());
+
+    println!("speedup: {:.2}x", after / before);
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,145 @@
+use std::{
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Barrier,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use serde::Serialize;
+use hash_forge::error::{Error, Result};
+use hash_forge::hash_algo::HashAlgorithm;
+use hash_forge::registry::ALGORITHMS;
+use hash_forge::{create_generator, create_verifier, HashMode};
+use crate::{BenchmarkArgs, BenchmarkFormat, ScryptCommand};
+
+/// Throughput of one mode at one thread count.
+#[derive(Serialize)]
+struct BenchResult {
+    mode: String,
+    algorithm: String,
+    params: Option<String>,
+    threads: usize,
+    hashes: u64,
+    seconds: f64,
+    hashes_per_second: f64,
+}
+
+fn generator(args: &BenchmarkArgs, mode: &HashMode) -> Result<Box<dyn HashAlgorithm>> {
+    let cost = if *mode == HashMode::BCRYPT {
+        args.bcrypt_cost
+    } else if *mode == HashMode::PHPASS {
+        args.phpass_count
+    } else {
+        None
+    };
+    create_generator(mode, cost, args.scrypt.as_ref().map(ScryptCommand::params))
+}
+
+/// Hashes synthetic candidates on one thread until `stop` is raised and
+/// returns how many were checked. The clock starts once every thread is
+/// past `ready`, so parsing the target isn't timed.
+fn worker(mode: &HashMode, target: &str, ready: &Barrier, stop: &AtomicBool) -> Result<u64> {
+    let verifier = create_verifier(mode, target, None);
+    ready.wait();
+    let verifier = verifier?;
+
+    let mut candidate = Vec::with_capacity(32);
+    let mut count = 0u64;
+    while !stop.load(Ordering::Relaxed) {
+        candidate.clear();
+        write!(candidate, "candidate{}", count).expect("writing to a Vec can't fail");
+        std::hint::black_box(verifier.verify(&candidate));
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn measure(args: &BenchmarkArgs, mode: &HashMode, threads: usize) -> Result<BenchResult> {
+    // Verify against a real target so the full check path is timed; making
+    // it costs a whole slow hash, so it's done before the clock starts
+    let hasher = generator(args, mode)?;
+    let target = hasher.encode(b"hash_forge");
+    let ready = Barrier::new(threads + 1);
+    let stop = AtomicBool::new(false);
+    let duration = Duration::from_secs_f64(args.duration);
+
+    let (counts, seconds) = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|_| scope.spawn(|| worker(mode, &target, &ready, &stop)))
+            .collect();
+        ready.wait();
+        let start = Instant::now();
+        thread::sleep(duration);
+        stop.store(true, Ordering::Relaxed);
+        let counts = handles
+            .into_iter()
+            .map(|h| h.join().expect("benchmark thread panicked"))
+            .collect::<Result<Vec<_>>>();
+        // Slow hashes finish their last candidate after the deadline, so time to the join
+        (counts, start.elapsed().as_secs_f64())
+    });
+    let hashes = counts?.iter().sum();
+
+    Ok(BenchResult {
+        mode: mode.name.to_string(),
+        algorithm: hasher.name().to_string(),
+        params: hasher.params(),
+        threads,
+        hashes,
+        seconds,
+        hashes_per_second: hashes as f64 / seconds,
+    })
+}
+
+/// Runs every requested mode at every requested thread count and prints
+/// hashes per second as a table or JSON.
+pub fn run(args: &BenchmarkArgs) -> Result<()> {
+    if args.duration <= 0.0 {
+        return Err(Error::Param("--duration must be positive".into()));
+    }
+    let modes: Vec<HashMode> = if args.mode.is_empty() {
+        ALGORITHMS.to_vec()
+    } else {
+        args.mode.clone()
+    };
+    let mut thread_counts = match &args.threads {
+        Some(threads) => threads.clone(),
+        None => {
+            let cores = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+            vec![1, cores]
+        }
+    };
+    thread_counts.dedup();
+    if thread_counts.contains(&0) {
+        return Err(Error::Param("--threads must be at least 1".into()));
+    }
+
+    if args.format == BenchmarkFormat::Table {
+        println!("{:<8} {:>7} {:>16}  Parameters", "Mode", "Threads", "H/s");
+    }
+    let mut results = Vec::new();
+    for mode in &modes {
+        for &threads in &thread_counts {
+            let result = measure(args, mode, threads)?;
+            if args.format == BenchmarkFormat::Table {
+                println!(
+                    "{:<8} {:>7} {:>16.1}  {}",
+                    result.mode,
+                    result.threads,
+                    result.hashes_per_second,
+                    result.params.as_deref().unwrap_or("-")
+                );
+            }
+            results.push(result);
+        }
+    }
+
+    if args.format == BenchmarkFormat::Json {
+        let json = serde_json::to_string_pretty(&results)
+            .map_err(|e| Error::Io { context: "Error encoding results".into(), source: e.into() })?;
+        println!("{}", json);
+    }
+    Ok(())
+}
@@ -0,0 +1,105 @@
+//! Combinator attack: every word of the left wordlist joined with every
+//! word of the right one, through the dictionary attack's expansion hook.
+
+use crate::crack::Expand;
+use crate::rules::Rule;
+
+/// Expands each left word into `rule(left) + separator + rule(right)` for
+/// every left rule, separator, right word and right rule, in that order
+/// of nesting.
+pub struct Combinator {
+    left_rules: Vec<Rule>,
+    separators: Vec<Vec<u8>>,
+    /// Right words with the right rules already applied
+    right: Vec<Vec<u8>>,
+}
+
+impl Combinator {
+    /// Empty rule or separator lists stand for the word as it is and no
+    /// separator.
+    pub fn new(right_words: &[Vec<u8>], left_rules: Vec<Rule>, right_rules: &[Rule], separators: Vec<Vec<u8>>) -> Self {
+        let left_rules = if left_rules.is_empty() { vec![Rule::default()] } else { left_rules };
+        let separators = if separators.is_empty() { vec![Vec::new()] } else { separators };
+        let right = match right_rules {
+            [] => right_words.to_vec(),
+            rules => right_words
+                .iter()
+                .flat_map(|word| rules.iter().map(|rule| rule.apply(word)))
+                .collect(),
+        };
+        Self { left_rules, separators, right }
+    }
+}
+
+impl Expand for Combinator {
+    fn count(&self) -> u64 {
+        (self.left_rules.len() * self.separators.len() * self.right.len()) as u64
+    }
+
+    fn apply(&self, word: &[u8], n: u64, out: &mut Vec<u8>) {
+        let n = n as usize;
+        let (rest, right) = (n / self.right.len(), n % self.right.len());
+        let (left, separator) = (rest / self.separators.len(), rest % self.separators.len());
+        out.extend(self.left_rules[left].apply(word));
+        out.extend_from_slice(&self.separators[separator]);
+        out.extend_from_slice(&self.right[right]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(words: &[&str]) -> Vec<Vec<u8>> {
+        words.iter().map(|word| word.as_bytes().to_vec()).collect()
+    }
+
+    fn rules(rules: &[&str]) -> Vec<Rule> {
+        rules.iter().map(|rule| rule.parse().unwrap()).collect()
+    }
+
+    fn all(combinator: &Combinator, left: &str) -> Vec<String> {
+        (0..combinator.count())
+            .map(|n| {
+                let mut out = Vec::new();
+                combinator.apply(left.as_bytes(), n, &mut out);
+                String::from_utf8(out).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_right_word_follows_the_left_one() {
+        let combinator = Combinator::new(&words(&["blue", "red", "42"]), Vec::new(), &[], Vec::new());
+        assert_eq!(combinator.count(), 3);
+        assert_eq!(all(&combinator, "sky"), ["skyblue", "skyred", "sky42"]);
+    }
+
+    #[test]
+    fn right_words_change_fastest_then_separators_then_left_rules() {
+        let combinator = Combinator::new(
+            &words(&["a", "b"]),
+            rules(&[":", "u"]),
+            &[],
+            words(&["", "-", "_"]),
+        );
+        assert_eq!(combinator.count(), 2 * 3 * 2);
+        assert_eq!(
+            all(&combinator, "x"),
+            ["xa", "xb", "x-a", "x-b", "x_a", "x_b", "Xa", "Xb", "X-a", "X-b", "X_a", "X_b"]
+        );
+    }
+
+    #[test]
+    fn rules_apply_to_their_own_side() {
+        let combinator = Combinator::new(
+            &words(&["dog", "cat"]),
+            rules(&["c"]),
+            &rules(&["$1", "u"]),
+            words(&[" "]),
+        );
+        assert_eq!(combinator.count(), 4);
+        // Each right word goes through every right rule before the next word
+        assert_eq!(all(&combinator, "hello"), ["Hello dog1", "Hello DOG", "Hello cat1", "Hello CAT"]);
+    }
+}
@@ -0,0 +1,537 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::encoding::Transcoder;
+use crate::error::{exit, Error};
+use crate::hash_algo::{HashAlgorithm, Verifier};
+use crate::wordlist::{self, Compression, Source};
+
+/// How far a run has got through its candidate sources. In single mode
+/// `source` counts targets and `offset` the candidates of that target.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Position {
+    /// Index into the expanded `--file` source list
+    pub source: usize,
+    /// Bytes of that source already processed, on the decompressed stream
+    pub offset: u64,
+    /// Lines of that source already processed
+    pub line: u64,
+    /// Candidates tried over the whole run
+    pub tried: u64,
+    /// Candidates consumed over the whole run, including those passed over by `--skip`
+    #[serde(default)]
+    pub index: u64,
+    /// Candidates already made from the next line, for attacks that make
+    /// several from each
+    #[serde(default)]
+    pub variant: u64,
+}
+
+/// How a run ended: `Cracked` once every target is, `Exhausted` when some
+/// are left after the last candidate.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Cracked,
+    Exhausted,
+    Aborted,
+}
+
+impl Outcome {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Outcome::Cracked => exit::CRACKED,
+            Outcome::Exhausted => exit::EXHAUSTED,
+            Outcome::Aborted => exit::ABORTED,
+        }
+    }
+}
+
+/// Whether a run should keep going, returned from [`Events::poll`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Control {
+    Continue,
+    Stop,
+}
+
+/// A candidate that matched a target.
+pub struct Hit<'a> {
+    /// Index into [`Cracker::targets`]
+    pub target: usize,
+    pub plaintext: &'a [u8],
+    /// Wordlist the plaintext was read from, `None` in single mode
+    pub source: Option<&'a Source>,
+    /// Line of `source` the plaintext was read from; in single mode, its
+    /// number among the candidates of the target
+    pub line: u64,
+    /// Position of the plaintext in the whole keyspace, counting from 1
+    pub index: u64,
+}
+
+/// What a finished run returns.
+#[derive(Debug)]
+pub struct Report {
+    pub outcome: Outcome,
+    /// Candidates tried, including those of the run being resumed
+    pub tried: u64,
+    /// Targets cracked by this run, by index, with their plaintexts
+    pub cracked: Vec<(usize, Vec<u8>)>,
+}
+
+/// Callbacks a run reports its progress through. Every method defaults to
+/// doing nothing, so implementors only pick the ones they care about.
+pub trait Events {
+    /// Called before every line is read; `position` is what a checkpoint
+    /// would save. Returning `Control::Stop` aborts the run there.
+    fn poll(&mut self, _position: &Position) -> Control {
+        Control::Continue
+    }
+
+    /// Source `index` of the run was opened.
+    fn source_started(&mut self, _index: usize, _source: &Source, _compression: Compression) {}
+
+    /// A resumed run skipped to `line` (byte `offset`) of `source`.
+    fn resumed(&mut self, _source: &Source, _line: u64, _offset: u64) {}
+
+    /// `word` is about to be tried; `offset` is where its line starts.
+    fn candidate(&mut self, _word: &[u8], _offset: u64) {}
+
+    fn cracked(&mut self, _hit: &Hit) {}
+
+    /// Done with `source`; `skipped` candidates couldn't be transcoded.
+    fn source_finished(&mut self, _source: &Source, _tried: u64, _skipped: u64) {}
+
+    /// The run stopped at its limit after consuming `index` candidates.
+    fn limit_reached(&mut self, _index: u64) {}
+
+    /// A source that can't be opened or read. The run carries on with the
+    /// next source.
+    fn error(&mut self, _error: &Error) {}
+}
+
+impl Events for () {}
+
+/// Makes several candidates from each wordlist line, for attacks built on
+/// the dictionary one.
+pub trait Expand {
+    /// Candidates made from every line
+    fn count(&self) -> u64;
+
+    /// Appends candidate `n`, below `count()`, made from `word` to `out`.
+    fn apply(&self, word: &[u8], n: u64, out: &mut Vec<u8>);
+}
+
+/// A dictionary attack: every line of every source, in order, is tried
+/// against the targets until all of them are cracked.
+pub struct Cracker<'a> {
+    /// Targets of one mode; the same hash may appear more than once
+    pub targets: Vec<&'a dyn Verifier>,
+    /// Turns each line into several candidates instead of trying it as is
+    pub expand: Option<&'a dyn Expand>,
+    pub transcoder: Transcoder,
+    /// Strip whitespace around every line
+    pub trim: bool,
+    /// Candidates to pass over at the start of the keyspace
+    pub skip: u64,
+    /// Candidates to try after `skip`, all of them when `None`
+    pub limit: Option<u64>,
+}
+
+impl<'a> Cracker<'a> {
+    pub fn new(targets: Vec<&'a dyn Verifier>) -> Self {
+        Self {
+            targets,
+            expand: None,
+            transcoder: Transcoder::default(),
+            trim: false,
+            skip: 0,
+            limit: None,
+        }
+    }
+
+    /// Runs through `sources` from `resume`, or from the start with
+    /// `Position::default()`.
+    pub fn run(&self, sources: &[Source], resume: &Position, events: &mut impl Events) -> Report {
+        let mut total_tried = resume.tried;
+        let mut index = resume.index;
+        let end = self.limit.map(|limit| self.skip.saturating_add(limit));
+        let per_line = self.expand.map_or(1, |expand| expand.count());
+        let mut buf = Vec::new();
+        let mut remaining = Remaining::new(&self.targets);
+        let mut cracked = Vec::new();
+        if remaining.is_empty() {
+            return Report { outcome: Outcome::Cracked, tried: total_tried, cracked };
+        }
+
+        'sources: for (n, source) in sources.iter().enumerate().skip(resume.source) {
+            let (mut lines, compression) = match source.open() {
+                Ok(r) => r,
+                Err(e) => {
+                    events.error(&e);
+                    continue;
+                }
+            };
+            events.source_started(n, source, compression);
+
+            let mut tried = 0;
+            let mut skipped = 0;
+            let mut line_no = 0;
+            if n == resume.source && resume.offset > 0 {
+                if let Err(e) = lines.skip_to(resume.offset) {
+                    let context = format!("Error resuming {} at byte {}", source, resume.offset);
+                    events.error(&Error::Io { context, source: e });
+                    continue;
+                }
+                line_no = resume.line;
+                events.resumed(source, line_no, resume.offset);
+            }
+
+            // Candidates of the next line already tried, when resuming inside it
+            let mut first = if n == resume.source { resume.variant } else { 0 };
+            loop {
+                // Offset before this line; `line` borrows the reader below
+                let offset = lines.position();
+                let position = Position {
+                    source: n,
+                    offset,
+                    line: line_no,
+                    tried: total_tried,
+                    index,
+                    variant: first,
+                };
+                if events.poll(&position) == Control::Stop {
+                    return Report { outcome: Outcome::Aborted, tried: total_tried, cracked };
+                }
+                if end.is_some_and(|end| index >= end) {
+                    events.source_finished(source, tried, skipped);
+                    events.limit_reached(index);
+                    break 'sources;
+                }
+
+                let line = match lines.next_line() {
+                    Ok(Some(line)) => line,
+                    Ok(None) => break,
+                    Err(e) => {
+                        // Truncated or corrupt compressed data, nothing more to read
+                        let context = format!("Error reading {} after line {}", source, line_no);
+                        events.error(&Error::Io { context, source: e });
+                        break;
+                    }
+                };
+                // Lines wholly inside --skip aren't expanded at all
+                let left = per_line.saturating_sub(first);
+                if index + left <= self.skip {
+                    index += left;
+                    line_no += 1;
+                    first = 0;
+                    continue;
+                }
+                let word = wordlist::candidate(line, self.trim);
+                for variant in first..per_line {
+                    if variant > first {
+                        let position = Position { variant, tried: total_tried, index, ..position };
+                        if events.poll(&position) == Control::Stop {
+                            return Report { outcome: Outcome::Aborted, tried: total_tried, cracked };
+                        }
+                        if end.is_some_and(|end| index >= end) {
+                            events.source_finished(source, tried, skipped);
+                            events.limit_reached(index);
+                            break 'sources;
+                        }
+                    }
+                    index += 1;
+                    if index <= self.skip {
+                        continue;
+                    }
+                    let candidate = match self.expand {
+                        Some(expand) => {
+                            buf.clear();
+                            expand.apply(&word, variant, &mut buf);
+                            &buf[..]
+                        }
+                        None => &word[..],
+                    };
+                    tried += 1;
+                    total_tried += 1;
+                    events.candidate(candidate, offset);
+
+                    let Some(encoded) = self.transcoder.apply(candidate) else {
+                        skipped += 1;
+                        continue;
+                    };
+                    let found = remaining.check(&self.targets, &encoded);
+                    for &target in &found {
+                        let line = line_no + 1;
+                        events.cracked(&Hit { target, plaintext: candidate, source: Some(source), line, index });
+                        cracked.push((target, candidate.to_vec()));
+                    }
+                    if !found.is_empty() && remaining.is_empty() {
+                        return Report { outcome: Outcome::Cracked, tried: total_tried, cracked };
+                    }
+                }
+                line_no += 1;
+                first = 0;
+            }
+            events.source_finished(source, tried, skipped);
+        }
+
+        Report { outcome: Outcome::Exhausted, tried: total_tried, cracked }
+    }
+
+    /// Single mode: `candidates[i]` are tried against target `i` only, in
+    /// order, until it is cracked. `None` marks a target that is already,
+    /// e.g. by the run being resumed.
+    pub fn run_single(&self, candidates: &[Option<Vec<Vec<u8>>>], resume: &Position, events: &mut impl Events) -> Report {
+        let mut total_tried = resume.tried;
+        let mut index = resume.index;
+        let end = self.limit.map(|limit| self.skip.saturating_add(limit));
+        let mut cracked = Vec::new();
+        let mut left = candidates.iter().flatten().count();
+        if left == 0 {
+            return Report { outcome: Outcome::Cracked, tried: total_tried, cracked };
+        }
+
+        for (target, words) in candidates.iter().enumerate().skip(resume.source) {
+            let Some(words) = words else { continue };
+            let start = if target == resume.source { resume.offset } else { 0 };
+            for (n, word) in (0u64..).zip(words).skip(start as usize) {
+                let position = Position { source: target, offset: n, line: 0, tried: total_tried, index, variant: 0 };
+                if events.poll(&position) == Control::Stop {
+                    return Report { outcome: Outcome::Aborted, tried: total_tried, cracked };
+                }
+                if end.is_some_and(|end| index >= end) {
+                    events.limit_reached(index);
+                    return Report { outcome: Outcome::Exhausted, tried: total_tried, cracked };
+                }
+
+                index += 1;
+                if index <= self.skip {
+                    continue;
+                }
+                total_tried += 1;
+                events.candidate(word, n);
+                let Some(encoded) = self.transcoder.apply(word) else { continue };
+                if self.targets[target].verify(&encoded) {
+                    events.cracked(&Hit { target, plaintext: word, source: None, line: n + 1, index });
+                    cracked.push((target, word.clone()));
+                    left -= 1;
+                    if left == 0 {
+                        return Report { outcome: Outcome::Cracked, tried: total_tried, cracked };
+                    }
+                    break;
+                }
+            }
+        }
+
+        Report { outcome: Outcome::Exhausted, tried: total_tried, cracked }
+    }
+}
+
+/// Targets not cracked yet. Raw digests sharing a hasher are looked up in a
+/// map after hashing the candidate once; the rest are verified one by one.
+struct Remaining<'a> {
+    hasher: Option<&'a dyn HashAlgorithm>,
+    digests: HashMap<&'a [u8], Vec<usize>>,
+    others: Vec<usize>,
+}
+
+impl<'a> Remaining<'a> {
+    fn new(targets: &[&'a dyn Verifier]) -> Self {
+        let mut remaining = Self { hasher: None, digests: HashMap::new(), others: Vec::new() };
+        for (i, target) in targets.iter().enumerate() {
+            match target.digest() {
+                Some((hasher, digest)) if remaining.shares_hasher(hasher) => {
+                    remaining.hasher = Some(hasher);
+                    remaining.digests.entry(digest).or_default().push(i);
+                }
+                _ => remaining.others.push(i),
+            }
+        }
+        remaining
+    }
+
+    fn shares_hasher(&self, hasher: &dyn HashAlgorithm) -> bool {
+        self.hasher
+            .is_none_or(|first| first.name() == hasher.name() && first.params() == hasher.params())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.digests.is_empty() && self.others.is_empty()
+    }
+
+    /// Targets `candidate` cracks; they are no longer remaining.
+    fn check(&mut self, targets: &[&dyn Verifier], candidate: &[u8]) -> Vec<usize> {
+        let mut found = Vec::new();
+        if let Some(hasher) = self.hasher
+            && !self.digests.is_empty()
+            && let Some(hits) = self.digests.remove(hasher.hash(candidate).as_slice())
+        {
+            found = hits;
+        }
+        self.others.retain(|&i| {
+            let matched = targets[i].verify(candidate);
+            if matched {
+                found.push(i);
+            }
+            !matched
+        });
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf, process};
+    use super::*;
+    use crate::combinator::Combinator;
+    use crate::hybrid::{Hybrid, Side};
+    use crate::registry::{create_verifier, HashMode};
+
+    /// Two wordlists, 5 lines in all, in a directory of their own.
+    fn wordlists(test: &str) -> (PathBuf, Vec<Source>) {
+        let dir = env::temp_dir().join(format!("hash_forge_{}_{}", test, process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let sources = [("a.txt", "alpha\nbeta\ngamma\n"), ("b.txt", "delta\nepsilon\n")]
+            .iter()
+            .map(|(name, words)| {
+                let path = dir.join(name);
+                fs::write(&path, words).unwrap();
+                Source::File(path)
+            })
+            .collect();
+        (dir, sources)
+    }
+
+    /// Every candidate tried, every position polled and every hit's index.
+    #[derive(Default)]
+    struct Recorder {
+        tried: Vec<Vec<u8>>,
+        positions: Vec<Position>,
+        hits: Vec<u64>,
+    }
+
+    impl Events for Recorder {
+        fn poll(&mut self, position: &Position) -> Control {
+            self.positions.push(position.clone());
+            Control::Continue
+        }
+
+        fn candidate(&mut self, word: &[u8], _offset: u64) {
+            self.tried.push(word.to_vec());
+        }
+
+        fn cracked(&mut self, hit: &Hit) {
+            self.hits.push(hit.index);
+        }
+    }
+
+    /// Runs against `target`, an MD5 hex digest, or one nothing matches.
+    fn run(
+        sources: &[Source],
+        expand: Option<&dyn Expand>,
+        (skip, limit): (u64, Option<u64>),
+        resume: &Position,
+        target: Option<&str>,
+    ) -> (Report, Recorder) {
+        let target = target.map_or_else(|| "0".repeat(32), str::to_string);
+        let verifier = create_verifier(&HashMode::MD5, &target, None).unwrap();
+        let mut cracker = Cracker::new(vec![verifier.as_ref()]);
+        cracker.expand = expand;
+        cracker.skip = skip;
+        cracker.limit = limit;
+        let mut recorder = Recorder::default();
+        let report = cracker.run(sources, resume, &mut recorder);
+        (report, recorder)
+    }
+
+    fn md5(word: &str) -> String {
+        hex::encode(md5::compute(word).0)
+    }
+
+    #[test]
+    fn keyspace_is_lines_times_candidates_per_line() {
+        let (dir, sources) = wordlists("keyspace");
+        let rules = Combinator::new(
+            &[b"1".to_vec(), b"2".to_vec()],
+            vec!["c".parse().unwrap(), "u".parse().unwrap()],
+            &["$!".parse().unwrap()],
+            vec![b"-".to_vec()],
+        );
+        let append = Hybrid::new("?d?d".parse().unwrap(), Side::Append);
+        let prepend = Hybrid::new("?l".parse().unwrap(), Side::Prepend);
+        let lines = wordlist::keyspace(&sources).unwrap();
+        assert_eq!(lines, 5);
+
+        let attacks: [(Option<&dyn Expand>, u64); 4] =
+            [(None, 1), (Some(&rules), 4), (Some(&append), 100), (Some(&prepend), 26)];
+        for (expand, per_line) in attacks {
+            assert_eq!(expand.map_or(1, |expand| expand.count()), per_line);
+            let (report, recorder) = run(&sources, expand, (0, None), &Position::default(), None);
+            assert_eq!(report.outcome, Outcome::Exhausted);
+            assert_eq!(report.tried, lines * per_line);
+            assert_eq!(recorder.tried.len() as u64, lines * per_line);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn slices_cover_the_keyspace_once() {
+        let (dir, sources) = wordlists("slices");
+        let hybrid = Hybrid::new("?d".parse().unwrap(), Side::Append);
+        for expand in [None, Some(&hybrid as &dyn Expand)] {
+            let (_, all) = run(&sources, expand, (0, None), &Position::default(), None);
+            let keyspace = all.tried.len() as u64;
+            for size in [1, 3, 7, 10, keyspace - 1, keyspace, keyspace + 1] {
+                let mut sliced = Vec::new();
+                for skip in (0..keyspace).step_by(size as usize) {
+                    let (report, slice) = run(&sources, expand, (skip, Some(size)), &Position::default(), None);
+                    assert_eq!(report.tried, size.min(keyspace - skip), "skip {} limit {}", skip, size);
+                    sliced.extend(slice.tried);
+                }
+                assert_eq!(sliced, all.tried, "slices of {}", size);
+            }
+            // A slice past the end tries nothing
+            let (report, _) = run(&sources, expand, (keyspace, Some(5)), &Position::default(), None);
+            assert_eq!(report.tried, 0);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn hits_count_from_one_across_slices() {
+        let (dir, sources) = wordlists("hits");
+        let hybrid = Hybrid::new("?d".parse().unwrap(), Side::Append);
+        // beta is line 2, so beta7 is candidate 10 + 7 + 1
+        let target = md5("beta7");
+        for slice in [(0, None), (17, Some(1)), (15, Some(5))] {
+            let (report, recorder) = run(&sources, Some(&hybrid), slice, &Position::default(), Some(&target));
+            assert_eq!(report.outcome, Outcome::Cracked, "slice {:?}", slice);
+            assert_eq!(recorder.hits, [18]);
+        }
+        let (report, _) = run(&sources, Some(&hybrid), (18, None), &Position::default(), Some(&target));
+        assert_eq!(report.outcome, Outcome::Exhausted);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn resumes_inside_a_line() {
+        let (dir, sources) = wordlists("resume");
+        let hybrid = Hybrid::new("?d".parse().unwrap(), Side::Prepend);
+        let (_, full) = run(&sources, Some(&hybrid), (0, None), &Position::default(), None);
+        let inside: Vec<&Position> = full.positions.iter().filter(|position| position.variant > 0).collect();
+        assert!(inside.iter().any(|position| position.source == 1 && position.offset > 0));
+
+        for position in inside {
+            let rest = &full.tried[position.index as usize..];
+            let (report, resumed) = run(&sources, Some(&hybrid), (0, None), position, None);
+            assert_eq!(resumed.tried, rest, "resumed at {:?}", position);
+            assert_eq!(report.tried, full.tried.len() as u64);
+
+            // A sliced run picks up inside its slice too
+            let (skip, limit) = (position.index.saturating_sub(3), 8);
+            let end = (skip + limit).min(full.tried.len() as u64) as usize;
+            let (_, resumed) = run(&sources, Some(&hybrid), (skip, Some(limit)), position, None);
+            assert_eq!(resumed.tried, full.tried[position.index as usize..end], "resumed at {:?}", position);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,109 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    iter,
+    net::TcpStream,
+};
+use clap::Parser;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use hash_forge::error::{Error, Result};
+use hash_forge::wordlist::{self, Source};
+use crate::Cli;
+
+pub mod server;
+pub mod worker;
+
+/// Messages a worker sends; every one of them gets exactly one reply.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkerMsg {
+    Hello { name: String },
+    /// Asks for the next chunk
+    Request,
+    /// Heartbeat with the candidates tried so far in the current chunk
+    Progress { chunk: u64, tried: u64 },
+    Done { chunk: u64, tried: u64, found: Option<Found> },
+}
+
+/// A match reported by a worker; the server re-checks it before trusting it.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Found {
+    /// Position of the candidate in the job's keyspace, counting from 1
+    pub index: u64,
+    pub plaintext_hex: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMsg {
+    /// Crack command line every chunk is run with, sent in reply to `Hello`
+    Job { args: Vec<String> },
+    /// Candidates `skip..skip + limit` of the job's keyspace
+    Chunk { id: u64, skip: u64, limit: u64 },
+    /// Nothing to hand out right now, but chunks may still be re-issued
+    Wait { secs: u64 },
+    Ack,
+    /// The job is over, disconnect
+    Stop,
+}
+
+/// One end of a connection; messages are single lines of JSON.
+pub struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    line: String,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            line: String::new(),
+        })
+    }
+
+    pub fn peer(&self) -> String {
+        self.writer
+            .peer_addr()
+            .map(|addr| addr.to_string())
+            .unwrap_or_else(|_| "?".into())
+    }
+
+    pub fn send<T: Serialize>(&mut self, msg: &T) -> io::Result<()> {
+        let mut data = serde_json::to_vec(msg)?;
+        data.push(b'\n');
+        self.writer.write_all(&data)
+    }
+
+    pub fn recv<T: DeserializeOwned>(&mut self) -> io::Result<T> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(serde_json::from_str(&self.line)?)
+    }
+}
+
+/// Parses a job's crack command line (everything after the program name)
+/// and checks it can be split across workers.
+pub fn parse_job(args: &[String]) -> Result<(Cli, Vec<Source>)> {
+    let cli = Cli::try_parse_from(iter::once("hash_forge").chain(args.iter().map(String::as_str)))
+        .map_err(|e| Error::Parse(format!("Invalid job: {}", e.to_string().trim_end())))?;
+    if cli.hash_file.is_some() || cli.single {
+        return Err(Error::Unsupported("Invalid job: --hash-file and --single can't be distributed, give one --hash".into()));
+    }
+    if cli.file.is_empty() || cli.hash.is_none() || cli.mode.is_none() {
+        return Err(Error::Param("Invalid job: --file, --hash and --mode are required".into()));
+    }
+    if cli.restore.is_some() || cli.keyspace {
+        return Err(Error::Unsupported("Invalid job: --restore and --keyspace can't be distributed".into()));
+    }
+    if cli.command.is_some() && cli.scrypt_params().is_none() {
+        return Err(Error::Unsupported("Invalid job: only the scrypt parameters subcommand is allowed".into()));
+    }
+    let sources = wordlist::expand_sources(&cli.file)?;
+    if sources.iter().any(|source| matches!(source, Source::Stdin)) {
+        return Err(Error::Unsupported("Invalid job: stdin can't be shared between workers".into()));
+    }
+    Ok((cli, sources))
+}
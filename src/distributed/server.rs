@@ -0,0 +1,358 @@
+use std::{
+    collections::VecDeque,
+    io::{self, IsTerminal, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+use super::{parse_job, Connection, Found, ServerMsg, WorkerMsg};
+use hash_forge::error::{Error, Result};
+use hash_forge::potfile::Potfile;
+use hash_forge::wordlist::display_candidate;
+use hash_forge::create_verifier;
+use crate::status::{format_duration, format_speed};
+use crate::{Cli, ServerArgs};
+
+/// How long the server waits for workers to pick up `Stop` once the job ends.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug)]
+struct Chunk {
+    id: u64,
+    skip: u64,
+    limit: u64,
+}
+
+/// Job progress shared by the connection threads.
+struct State {
+    pending: VecDeque<Chunk>,
+    in_flight: usize,
+    total_chunks: usize,
+    chunks_done: usize,
+    tried: u64,
+    workers: usize,
+    found: Option<Vec<u8>>,
+}
+
+impl State {
+    fn finished(&self) -> bool {
+        self.found.is_some() || (self.pending.is_empty() && self.in_flight == 0)
+    }
+
+    fn next_chunk(&mut self) -> (ServerMsg, Option<Chunk>) {
+        if self.found.is_some() {
+            return (ServerMsg::Stop, None);
+        }
+        match self.pending.pop_front() {
+            Some(chunk) => {
+                self.in_flight += 1;
+                let msg = ServerMsg::Chunk { id: chunk.id, skip: chunk.skip, limit: chunk.limit };
+                (msg, Some(chunk))
+            }
+            // Chunks held by other workers come back if those workers die
+            None if self.in_flight > 0 => (ServerMsg::Wait { secs: 1 }, None),
+            None => (ServerMsg::Stop, None),
+        }
+    }
+
+    fn reply(&self) -> ServerMsg {
+        if self.found.is_some() {
+            ServerMsg::Stop
+        } else {
+            ServerMsg::Ack
+        }
+    }
+}
+
+/// What every connection thread needs to know about the job.
+struct Job {
+    args: Vec<String>,
+    cli: Cli,
+    hash: String,
+    potfile: Potfile,
+    state: Mutex<State>,
+}
+
+/// Serves the job in `args.job` to workers until the hash is cracked or
+/// every chunk is done. Returns whether the hash was cracked.
+pub fn run(args: &ServerArgs) -> Result<bool> {
+    run_on(args, None)
+}
+
+/// [`run`], accepting workers on `listener` if given instead of binding
+/// `--listen`.
+fn run_on(args: &ServerArgs, listener: Option<TcpListener>) -> Result<bool> {
+    let (cli, _) = parse_job(&args.job)?;
+    let hash = cli.hash.as_deref().unwrap_or_default().trim().to_string();
+    let mode = cli.mode.as_ref().ok_or_else(|| Error::Param("Invalid job: --mode is required".into()))?;
+    // Catch malformed targets here rather than in every worker
+    create_verifier(mode, &hash, cli.scrypt_params())?;
+
+    let potfile = Potfile::new(&args.potfile);
+    if let Some(plaintext) = potfile.find(&hash)? {
+        println!("Already in {}: {}", potfile.path().display(), display_candidate(&plaintext));
+        return Ok(true);
+    }
+    if args.chunk_size == 0 {
+        return Err(Error::Param("--chunk-size must be at least 1".into()));
+    }
+
+    let keyspace = crate::keyspace(&cli)?;
+    let start = cli.skip.min(keyspace);
+    let end = cli.limit.map_or(keyspace, |limit| start.saturating_add(limit).min(keyspace));
+    let pending: VecDeque<Chunk> = (start..end)
+        .step_by(args.chunk_size as usize)
+        .enumerate()
+        .map(|(id, skip)| Chunk {
+            id: id as u64,
+            skip,
+            limit: args.chunk_size.min(end - skip),
+        })
+        .collect();
+
+    let listener = match listener {
+        Some(listener) => listener,
+        None => TcpListener::bind(&args.listen)
+            .map_err(Error::io(format!("Error listening on {}", args.listen)))?,
+    };
+    println!(
+        "Listening on {}: {} candidates in {} chunks of {}",
+        listener.local_addr().map_or_else(|_| args.listen.clone(), |addr| addr.to_string()),
+        end - start,
+        pending.len(),
+        args.chunk_size
+    );
+
+    let job = Arc::new(Job {
+        args: args.job.clone(),
+        cli,
+        hash,
+        potfile,
+        state: Mutex::new(State {
+            total_chunks: pending.len(),
+            pending,
+            in_flight: 0,
+            chunks_done: 0,
+            tried: 0,
+            workers: 0,
+            found: None,
+        }),
+    });
+
+    let timeout = Duration::from_secs(args.timeout.max(1));
+    let accepting = Arc::clone(&job);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let job = Arc::clone(&accepting);
+            thread::spawn(move || serve(stream, &job, timeout));
+        }
+    });
+
+    let inline = io::stderr().is_terminal();
+    let start_time = Instant::now();
+    let mut last = (Instant::now(), 0);
+    loop {
+        thread::sleep(Duration::from_secs(1));
+        let state = job.state.lock().unwrap();
+        let now = Instant::now();
+        let speed = (state.tried - last.1) as f64 / now.duration_since(last.0).as_secs_f64();
+        last = (now, state.tried);
+        let line = format!(
+            "[{}] chunks {}/{} | {} tried | {} | workers {} | cracked {}/1",
+            format_duration(start_time.elapsed()),
+            state.chunks_done,
+            state.total_chunks,
+            state.tried,
+            format_speed(speed),
+            state.workers,
+            state.found.is_some() as u8
+        );
+        if inline {
+            eprint!("\r\x1b[2K{}", line);
+            let _ = io::stderr().flush();
+        } else {
+            eprintln!("{}", line);
+        }
+        if state.finished() {
+            break;
+        }
+    }
+    if inline {
+        eprintln!();
+    }
+
+    // Give connected workers a chance to hear `Stop` before the process exits
+    let deadline = Instant::now() + SHUTDOWN_GRACE;
+    while job.state.lock().unwrap().workers > 0 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    let state = job.state.lock().unwrap();
+    match &state.found {
+        Some(plaintext) => {
+            println!("✅ Match found: {}", display_candidate(plaintext));
+            println!("Hash: {}", job.hash);
+            Ok(true)
+        }
+        None => {
+            println!("❌ No match found.");
+            Ok(false)
+        }
+    }
+}
+
+/// Handles one worker connection. A chunk the worker holds when it
+/// disconnects or goes quiet for `timeout` is put back in the queue.
+fn serve(stream: TcpStream, job: &Job, timeout: Duration) {
+    let _ = stream.set_read_timeout(Some(timeout));
+    let Ok(mut conn) = Connection::new(stream) else {
+        return;
+    };
+    let name = match conn.recv() {
+        Ok(WorkerMsg::Hello { name }) => format!("{} ({})", name, conn.peer()),
+        _ => return,
+    };
+    if conn.send(&ServerMsg::Job { args: job.args.clone() }).is_err() {
+        return;
+    }
+    job.state.lock().unwrap().workers += 1;
+    log::info!("Worker {} connected", name);
+
+    // The chunk this worker holds and how much of it was already counted
+    let mut current: Option<(Chunk, u64)> = None;
+    let result: io::Result<()> = loop {
+        let msg = match conn.recv() {
+            Ok(msg) => msg,
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+                let silent = format!("silent for {}s", timeout.as_secs());
+                break Err(io::Error::new(io::ErrorKind::TimedOut, silent));
+            }
+            Err(e) => break Err(e),
+        };
+        let reply = match msg {
+            WorkerMsg::Hello { .. } => ServerMsg::Ack,
+            WorkerMsg::Request => {
+                let (reply, chunk) = job.state.lock().unwrap().next_chunk();
+                current = chunk.map(|chunk| (chunk, 0));
+                reply
+            }
+            WorkerMsg::Progress { chunk, tried } => {
+                let mut state = job.state.lock().unwrap();
+                if let Some((_, counted)) = current.as_mut().filter(|(held, _)| held.id == chunk) {
+                    state.tried += tried.saturating_sub(*counted);
+                    *counted = tried.max(*counted);
+                }
+                state.reply()
+            }
+            WorkerMsg::Done { chunk, tried, found } => {
+                if let Some((held, counted)) = current.take_if(|(held, _)| held.id == chunk) {
+                    let plaintext = found.and_then(|found| confirm(job, &name, held, found));
+                    let mut state = job.state.lock().unwrap();
+                    state.tried += tried.saturating_sub(counted);
+                    state.in_flight -= 1;
+                    state.chunks_done += 1;
+                    if let Some(plaintext) = plaintext {
+                        if let Err(e) = job.potfile.append(&job.hash, &plaintext) {
+                            log::error!("{}", e);
+                        }
+                        state.found.get_or_insert(plaintext);
+                    }
+                }
+                job.state.lock().unwrap().reply()
+            }
+        };
+        let stop = matches!(reply, ServerMsg::Stop);
+        if let Err(e) = conn.send(&reply) {
+            break Err(e);
+        }
+        if stop {
+            break Ok(());
+        }
+    };
+
+    let mut state = job.state.lock().unwrap();
+    state.workers -= 1;
+    let requeued = match (&result, current) {
+        (Err(_), Some((chunk, _))) => {
+            state.in_flight -= 1;
+            state.pending.push_front(chunk);
+            Some(chunk.id)
+        }
+        // Stopped mid-chunk because the job is over
+        (Ok(()), Some(_)) => {
+            state.in_flight -= 1;
+            None
+        }
+        (_, None) => None,
+    };
+    drop(state);
+    match (result, requeued) {
+        (Err(e), Some(id)) => log::warn!("Worker {} lost ({}), chunk {} re-queued", name, e, id),
+        (Err(e), None) => log::warn!("Worker {} lost ({})", name, e),
+        (Ok(()), _) => log::info!("Worker {} finished", name),
+    }
+}
+
+/// Re-checks a reported match with the server's own verifier.
+fn confirm(job: &Job, name: &str, chunk: Chunk, found: Found) -> Option<Vec<u8>> {
+    let plaintext = hex::decode(&found.plaintext_hex).ok()?;
+    let mode = job.cli.mode.as_ref()?;
+    let verifier = create_verifier(mode, &job.hash, job.cli.scrypt_params()).ok()?;
+    let encoded = job.cli.encoding.transcoder().apply(&plaintext)?.into_owned();
+    if verifier.verify(&encoded) {
+        log::info!(
+            "Worker {} cracked the hash at candidate {} (chunk {})",
+            name, found.index, chunk.id
+        );
+        Some(plaintext)
+    } else {
+        log::warn!("Worker {} reported a match that doesn't verify, ignored", name);
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+    use hash_forge::{create_generator, HashMode};
+    use super::*;
+    use crate::distributed::worker;
+    use crate::WorkerArgs;
+
+    #[test]
+    fn workers_on_localhost_crack_a_hybrid_job() {
+        let dir = env::temp_dir().join(format!("hash_forge_distributed_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let words = dir.join("words.txt");
+        let potfile = dir.join("hash_forge.potfile");
+        fs::write(&words, (0..500).map(|n| format!("word{}\n", n)).collect::<String>()).unwrap();
+        // word317 with a 7 appended, candidate 3178 of 5000
+        let hash = create_generator(&HashMode::MD5, None, None).unwrap().encode(b"word3177");
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let words = words.to_str().unwrap();
+        let args = ServerArgs {
+            listen: addr.clone(),
+            chunk_size: 333,
+            potfile: potfile.clone(),
+            timeout: 30,
+            job: ["-f", words, "--append-mask", "?d", "--hash", &hash, "-m", "md5"].map(String::from).to_vec(),
+        };
+        let workers: Vec<_> = (0..2)
+            .map(|n| {
+                let args = WorkerArgs { connect: addr.clone(), name: Some(format!("test {}", n)) };
+                thread::spawn(move || worker::run(&args))
+            })
+            .collect();
+
+        let cracked = run_on(&args, Some(listener)).unwrap();
+        for worker in workers {
+            worker.join().unwrap().unwrap();
+        }
+        assert!(cracked);
+        assert_eq!(Potfile::new(&potfile).find(&hash).unwrap(), Some(b"word3177".to_vec()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
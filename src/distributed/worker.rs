@@ -0,0 +1,152 @@
+use std::{
+    io,
+    net::TcpStream,
+    process, thread,
+    time::{Duration, Instant},
+};
+use super::{parse_job, Connection, Found, ServerMsg, WorkerMsg};
+use hash_forge::error::{Error, Result};
+use hash_forge::{create_verifier, Control, Cracker, Events, Hit, Position};
+use crate::status::Pacer;
+use crate::WorkerArgs;
+
+/// How often a worker reports progress, which doubles as its heartbeat.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Runs one chunk: reports progress to the server and keeps what the
+/// cracker found.
+struct ChunkEvents<'a> {
+    conn: &'a mut Connection,
+    chunk: u64,
+    pacer: Pacer,
+    /// Where the run got to; the next chunk normally starts there
+    position: Position,
+    found: Option<Found>,
+    /// The server ended the job while the chunk was being worked on
+    stopped: bool,
+    failed: Option<Error>,
+}
+
+impl Events for ChunkEvents<'_> {
+    fn poll(&mut self, position: &Position) -> Control {
+        self.position = position.clone();
+        if self.failed.is_some() {
+            return Control::Stop;
+        }
+        if self.pacer.tick(position.tried).is_none() {
+            return Control::Continue;
+        }
+        match exchange(self.conn, &WorkerMsg::Progress { chunk: self.chunk, tried: position.tried }) {
+            Ok(ServerMsg::Stop) => {
+                self.stopped = true;
+                Control::Stop
+            }
+            Ok(_) => Control::Continue,
+            Err(e) => {
+                self.failed = Some(e);
+                Control::Stop
+            }
+        }
+    }
+
+    fn cracked(&mut self, hit: &Hit) {
+        self.found = Some(Found { index: hit.index, plaintext_hex: hex::encode(hit.plaintext) });
+    }
+
+    fn error(&mut self, error: &Error) {
+        // Carrying on would report the chunk done with part of it never tried
+        let context = format!("Error working on chunk {}", self.chunk);
+        self.failed.get_or_insert(Error::Io { context, source: io::Error::other(error.to_string()) });
+    }
+}
+
+/// Connects to a server and works on its chunks until told to stop.
+pub fn run(args: &WorkerArgs) -> Result<()> {
+    let stream = TcpStream::connect(&args.connect)
+        .map_err(Error::io(format!("Error connecting to {}", args.connect)))?;
+    let mut conn = Connection::new(stream).map_err(Error::io("Connection error"))?;
+    let name = args.name.clone().unwrap_or_else(|| format!("pid {}", process::id()));
+
+    let job = match exchange(&mut conn, &WorkerMsg::Hello { name })? {
+        ServerMsg::Job { args } => args,
+        other => return Err(Error::Parse(format!("Unexpected reply from server: {:?}", other))),
+    };
+    let (cli, sources) = parse_job(&job)?;
+    let hash = cli.hash.as_deref().unwrap_or_default().trim();
+    let mode = cli.mode.as_ref().ok_or_else(|| Error::Param("Invalid job: --mode is required".into()))?;
+    let verifier = create_verifier(mode, hash, cli.scrypt_params())?;
+    let expand = crate::expansion(&cli)?;
+    println!("Connected to {}: {}", conn.peer(), job.join(" "));
+
+    let mut cracker = Cracker::new(vec![verifier.as_ref()]);
+    cracker.expand = expand.as_deref();
+    cracker.transcoder = cli.encoding.transcoder();
+    cracker.trim = cli.trim;
+    // Chunks normally arrive in increasing order, so each picks up where
+    // the last one stopped instead of reading the wordlists from the start
+    let mut last = Position::default();
+    loop {
+        let (id, skip, limit) = match exchange(&mut conn, &WorkerMsg::Request)? {
+            ServerMsg::Chunk { id, skip, limit } => (id, skip, limit),
+            ServerMsg::Wait { secs } => {
+                thread::sleep(Duration::from_secs(secs));
+                continue;
+            }
+            ServerMsg::Stop => break,
+            other => return Err(Error::Parse(format!("Unexpected reply from server: {:?}", other))),
+        };
+
+        cracker.skip = skip;
+        cracker.limit = Some(limit);
+        let resume = match last.index == skip {
+            true => Position { tried: 0, ..last },
+            false => Position::default(),
+        };
+        let started = Instant::now();
+        let mut events = ChunkEvents {
+            conn: &mut conn,
+            chunk: id,
+            pacer: Pacer::new(PROGRESS_INTERVAL),
+            position: resume.clone(),
+            found: None,
+            stopped: false,
+            failed: None,
+        };
+        let report = cracker.run(&sources, &resume, &mut events);
+        let ChunkEvents { position, found, stopped, failed, .. } = events;
+        if let Some(e) = failed {
+            return Err(e);
+        }
+        if stopped {
+            println!("Chunk {} abandoned, the job is over", id);
+            return Ok(());
+        }
+        last = position;
+
+        let elapsed = started.elapsed().as_secs_f64().max(1e-3);
+        println!(
+            "Chunk {} ({}..{}): {} tried, {:.0} H/s",
+            id,
+            skip,
+            skip + limit,
+            report.tried,
+            report.tried as f64 / elapsed
+        );
+        if found.is_some() {
+            println!("✅ Match found in chunk {}", id);
+        }
+        let done = WorkerMsg::Done { chunk: id, tried: report.tried, found };
+        if let ServerMsg::Stop = exchange(&mut conn, &done)? {
+            break;
+        }
+    }
+    println!("Server has no more work, exiting");
+    Ok(())
+}
+
+/// Sends one message and waits for the server's reply.
+fn exchange(conn: &mut Connection, msg: &WorkerMsg) -> Result<ServerMsg> {
+    conn.send(msg)
+        .and_then(|_| conn.recv())
+        .map_err(Error::io("Lost connection to server"))
+}
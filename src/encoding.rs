@@ -0,0 +1,227 @@
+use std::{borrow::Cow, fmt, str::FromStr};
+use encoding_rs::Encoding;
+use unicode_normalization::UnicodeNormalization;
+
+/// Character encoding a candidate is read in or hashed as.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Charset {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// True ISO-8859-1, every byte maps to the code point of the same value
+    Latin1,
+    /// Single byte legacy encodings (ISO-8859-2..16, CP1252, ...)
+    Legacy(&'static Encoding),
+}
+
+impl Charset {
+    fn decode<'a>(&self, input: &'a [u8]) -> Option<Cow<'a, str>> {
+        match self {
+            Charset::Utf8 => std::str::from_utf8(input).ok().map(Cow::Borrowed),
+            Charset::Utf16Le | Charset::Utf16Be => {
+                if !input.len().is_multiple_of(2) {
+                    return None;
+                }
+                let units = input.chunks_exact(2).map(|pair| match self {
+                    Charset::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                    _ => u16::from_be_bytes([pair[0], pair[1]]),
+                });
+                char::decode_utf16(units)
+                    .collect::<Result<String, _>>()
+                    .ok()
+                    .map(Cow::Owned)
+            }
+            Charset::Latin1 => Some(Cow::Owned(input.iter().map(|&b| b as char).collect())),
+            Charset::Legacy(encoding) => encoding.decode_without_bom_handling_and_without_replacement(input),
+        }
+    }
+
+    fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        match self {
+            Charset::Utf8 => Some(text.as_bytes().to_vec()),
+            Charset::Utf16Le => Some(text.encode_utf16().flat_map(u16::to_le_bytes).collect()),
+            Charset::Utf16Be => Some(text.encode_utf16().flat_map(u16::to_be_bytes).collect()),
+            Charset::Latin1 => text.chars().map(|c| u8::try_from(c).ok()).collect(),
+            Charset::Legacy(encoding) => {
+                let (bytes, _, had_errors) = encoding.encode(text);
+                (!had_errors).then(|| bytes.into_owned())
+            }
+        }
+    }
+}
+
+impl FromStr for Charset {
+    type Err = String;
+
+    fn from_str(label: &str) -> Result<Self, Self::Err> {
+        let label = label.trim().to_ascii_lowercase();
+        match label.as_str() {
+            "utf-8" | "utf8" => Ok(Charset::Utf8),
+            "utf-16le" | "utf16le" | "utf-16" | "utf16" => Ok(Charset::Utf16Le),
+            "utf-16be" | "utf16be" => Ok(Charset::Utf16Be),
+            // encoding_rs follows WHATWG and maps these to windows-1252
+            "latin1" | "latin-1" | "iso-8859-1" | "iso8859-1" | "l1" => Ok(Charset::Latin1),
+            _ => match Encoding::for_label(label.as_bytes()) {
+                // Multi-byte encodings can't be encoded to, keep to single byte ones
+                Some(encoding) if encoding.is_single_byte() => Ok(Charset::Legacy(encoding)),
+                _ => Err(format!(
+                    "unsupported encoding '{}' (try utf-8, utf-16le, utf-16be, latin1, iso-8859-2..16, cp1252)",
+                    label
+                )),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Charset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Charset::Utf8 => write!(f, "UTF-8"),
+            Charset::Utf16Le => write!(f, "UTF-16LE"),
+            Charset::Utf16Be => write!(f, "UTF-16BE"),
+            Charset::Latin1 => write!(f, "ISO-8859-1"),
+            Charset::Legacy(encoding) => write!(f, "{}", encoding.name()),
+        }
+    }
+}
+
+/// Unicode normalization form applied between decoding and encoding.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Normalization {
+    Nfc,
+    Nfd,
+}
+
+/// Converts wordlist candidates into the byte form the target was hashed over.
+pub struct Transcoder {
+    from: Charset,
+    to: Charset,
+    normalize: Option<Normalization>,
+}
+
+impl Transcoder {
+    pub fn new(from: Charset, to: Charset, normalize: Option<Normalization>) -> Self {
+        Self { from, to, normalize }
+    }
+
+    /// True when candidates pass through unchanged.
+    pub fn is_identity(&self) -> bool {
+        self.from == self.to && self.normalize.is_none()
+    }
+
+    /// Re-encodes `input`, or returns `None` if it can't be decoded in the
+    /// source encoding or represented in the target one.
+    pub fn apply<'a>(&self, input: &'a [u8]) -> Option<Cow<'a, [u8]>> {
+        if self.is_identity() {
+            return Some(Cow::Borrowed(input));
+        }
+        let text = self.from.decode(input)?;
+        let text: Cow<str> = match self.normalize {
+            Some(Normalization::Nfc) => Cow::Owned(text.nfc().collect()),
+            Some(Normalization::Nfd) => Cow::Owned(text.nfd().collect()),
+            None => text,
+        };
+        self.to.encode(&text).map(Cow::Owned)
+    }
+}
+
+impl Default for Transcoder {
+    /// UTF-8 in, UTF-8 out: candidates are hashed as read.
+    fn default() -> Self {
+        Self::new(Charset::Utf8, Charset::Utf8, None)
+    }
+}
+
+impl fmt::Display for Transcoder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} -> {}", self.from, self.to)?;
+        if let Some(normalize) = self.normalize {
+            write!(f, " ({:?})", normalize)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn charset(label: &str) -> Charset {
+        label.parse().unwrap()
+    }
+
+    fn convert(from: &str, to: &str, input: &[u8]) -> Option<Vec<u8>> {
+        Transcoder::new(charset(from), charset(to), None).apply(input).map(Cow::into_owned)
+    }
+
+    fn normalize(form: Normalization, input: &str) -> String {
+        let transcoder = Transcoder::new(Charset::Utf8, Charset::Utf8, Some(form));
+        String::from_utf8(transcoder.apply(input.as_bytes()).unwrap().into_owned()).unwrap()
+    }
+
+    #[test]
+    fn labels_are_recognized() {
+        assert_eq!(charset("UTF-8"), Charset::Utf8);
+        assert_eq!(charset("utf16"), Charset::Utf16Le);
+        assert_eq!(charset("utf-16be"), Charset::Utf16Be);
+        assert_eq!(charset(" Latin1 "), Charset::Latin1);
+        assert_eq!(charset("cp1252"), Charset::Legacy(encoding_rs::WINDOWS_1252));
+        assert_eq!(charset("iso-8859-2"), Charset::Legacy(encoding_rs::ISO_8859_2));
+        // Multi-byte legacy encodings can't be encoded to
+        for label in ["shift_jis", "gbk", "ebcdic"] {
+            assert!(label.parse::<Charset>().is_err(), "{}", label);
+        }
+    }
+
+    #[test]
+    fn utf16_has_both_byte_orders() {
+        assert_eq!(convert("utf-8", "utf-16le", b"pw").unwrap(), b"p\0w\0");
+        assert_eq!(convert("utf-8", "utf-16be", b"pw").unwrap(), b"\0p\0w");
+        assert_eq!(convert("utf-8", "utf-16le", "€".as_bytes()).unwrap(), [0xac, 0x20]);
+        // Outside the BMP takes a surrogate pair
+        assert_eq!(convert("utf-8", "utf-16le", "😀".as_bytes()).unwrap(), [0x3d, 0xd8, 0x00, 0xde]);
+        assert_eq!(convert("utf-16be", "utf-8", &[0xd8, 0x3d, 0xde, 0x00]).unwrap(), "😀".as_bytes());
+        for text in ["pässwörd", "日本語", "😀!"] {
+            let wide = convert("utf-8", "utf-16le", text.as_bytes()).unwrap();
+            assert_eq!(convert("utf-16le", "utf-8", &wide).unwrap(), text.as_bytes());
+        }
+        // Odd lengths and unpaired surrogates don't decode
+        assert_eq!(convert("utf-16le", "utf-8", b"p\0w"), None);
+        assert_eq!(convert("utf-16le", "utf-8", &[0x3d, 0xd8]), None);
+    }
+
+    #[test]
+    fn latin1_and_cp1252_round_trip() {
+        assert_eq!(convert("utf-8", "latin1", "café".as_bytes()).unwrap(), b"caf\xe9");
+        assert_eq!(convert("latin1", "utf-8", b"caf\xe9").unwrap(), "café".as_bytes());
+        assert_eq!(convert("utf-8", "cp1252", "café".as_bytes()).unwrap(), b"caf\xe9");
+        // 0x80 is a C1 control in Latin-1 but the euro sign in CP1252
+        assert_eq!(convert("latin1", "utf-8", b"\x80").unwrap(), "\u{80}".as_bytes());
+        assert_eq!(convert("cp1252", "utf-8", b"\x80").unwrap(), "€".as_bytes());
+        assert_eq!(convert("utf-8", "cp1252", "€".as_bytes()).unwrap(), b"\x80");
+        let every_byte: Vec<u8> = (0..=255).collect();
+        let text = convert("latin1", "utf-8", &every_byte).unwrap();
+        assert_eq!(convert("utf-8", "latin1", &text).unwrap(), every_byte);
+    }
+
+    #[test]
+    fn nfc_composes_and_nfd_decomposes() {
+        let composed = "\u{e9}t\u{e9}";
+        let decomposed = "e\u{301}te\u{301}";
+        assert_eq!(normalize(Normalization::Nfc, decomposed), composed);
+        assert_eq!(normalize(Normalization::Nfd, composed), decomposed);
+        assert_eq!(normalize(Normalization::Nfc, composed), composed);
+        assert!(!Transcoder::new(Charset::Utf8, Charset::Utf8, Some(Normalization::Nfc)).is_identity());
+    }
+
+    #[test]
+    fn unmappable_candidates_are_skipped() {
+        assert_eq!(convert("utf-8", "latin1", "€".as_bytes()), None);
+        assert_eq!(convert("utf-8", "cp1252", "日本".as_bytes()), None);
+        assert_eq!(convert("utf-8", "iso-8859-2", "ß€".as_bytes()), None);
+        // Input that isn't valid in the source encoding
+        assert_eq!(convert("utf-8", "utf-16le", b"\xffpw"), None);
+        // Unless nothing is converted at all
+        assert_eq!(Transcoder::default().apply(b"\xff").unwrap(), &b"\xff"[..]);
+    }
+}
@@ -0,0 +1,64 @@
+use std::{fmt, io};
+
+/// Everything that can stop hash_forge from doing what it was asked.
+#[derive(Debug)]
+pub enum Error {
+    /// A hash, salt, parameter string or file line that can't be parsed
+    Parse(String),
+    /// Reading or writing a file, stream or socket failed
+    Io { context: String, source: io::Error },
+    /// A value outside the range an algorithm or option accepts
+    Param(String),
+    /// Input the selected mode or command doesn't handle
+    Unsupported(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// For `map_err`: wraps an `io::Error` with what was being done.
+    pub fn io(context: impl Into<String>) -> impl FnOnce(io::Error) -> Error {
+        let context = context.into();
+        move |source| Error::Io { context, source }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Parse(msg) | Error::Param(msg) | Error::Unsupported(msg) => write!(f, "{}", msg),
+            Error::Io { context, source } => write!(f, "{}: {}", context, source),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Process exit codes, the same for every command. Shown in `--help`.
+pub mod exit {
+    /// Every target was cracked, or `verify` matched
+    pub const CRACKED: i32 = 0;
+    /// Commands that don't crack anything finished without error
+    pub const SUCCESS: i32 = 0;
+    /// Every candidate was tried without a match, `verify` didn't match or
+    /// `identify` didn't recognize a hash
+    pub const EXHAUSTED: i32 = 1;
+    /// Bad arguments, unreadable input or any other error
+    pub const ERROR: i32 = 2;
+    /// Interrupted with Ctrl-C; the session can be restored
+    pub const ABORTED: i32 = 3;
+
+    pub const HELP: &str = "\
+Exit codes:
+  0  all targets cracked (verify: match; identify: all recognized; other commands: success)
+  1  exhausted with targets left uncracked (verify: no match; identify: unknown hash)
+  2  error, including invalid arguments
+  3  aborted with Ctrl-C, continue with --restore";
+}
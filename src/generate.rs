@@ -0,0 +1,31 @@
+use std::io;
+use hash_forge::error::{Error, Result};
+use hash_forge::create_generator;
+use hash_forge::wordlist::{candidate, read_line};
+use crate::{HashArgs, ScryptCommand};
+
+/// Prints the canonical encoding of `--password`, or of every stdin line
+/// (`$HEX[...]` lines are decoded first).
+pub fn run(args: &HashArgs) -> Result<()> {
+    let transcoder = args.encoding.transcoder();
+    let scrypt = args.scrypt.as_ref().map(ScryptCommand::params);
+    let encode = |password: &[u8]| -> Result<String> {
+        let encoded = transcoder
+            .apply(password)
+            .ok_or_else(|| Error::Unsupported(format!("Password not convertible {}", transcoder)))?;
+        // New generator per password so every salted hash gets its own salt
+        Ok(create_generator(&args.mode, args.cost, scrypt)?.encode(&encoded))
+    };
+
+    if let Some(password) = &args.password {
+        println!("{}", encode(password.as_bytes())?);
+        return Ok(());
+    }
+
+    let mut stdin = io::stdin().lock();
+    let mut line = Vec::new();
+    while read_line(&mut stdin, &mut line).map_err(Error::io("Error reading stdin"))? > 0 {
+        println!("{}", encode(&candidate(&line, false))?);
+    }
+    Ok(())
+}
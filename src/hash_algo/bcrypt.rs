@@ -1,32 +1,149 @@
-use bcrypt::verify;
-use super::HashAlgorithm;
+use std::sync::atomic::{AtomicBool, Ordering};
+use base64::Engine as _;
+use bcrypt::{hash_with_salt, HashParts, Version, BASE_64};
+use super::{random_bytes, HashAlgorithm, Verifier};
+use crate::error::{Error, Result};
 
+/// bcrypt only feeds the first 72 bytes of a password into its key schedule.
+const MAX_PASSWORD_LEN: usize = 72;
+
+/// bcrypt under one version, cost and salt.
 pub struct BcryptHash {
-    target_hash: String,
+    version: Version,
+    cost: u32,
+    salt: [u8; 16],
+    truncation_warned: AtomicBool,
 }
 
-impl BcryptHash {
-    pub fn new(target_hash: String) -> Self {
-        Self { target_hash }
+/// Verifier for `$2a$`, `$2b$` and `$2y$` modular crypt strings.
+///
+/// `$2x$` is rejected: crypt_blowfish marks hashes with it that were made
+/// by its old sign extension bug, which the bcrypt crate doesn't reproduce,
+/// so passwords with 8-bit characters would be checked wrongly.
+pub struct BcryptVerifier {
+    hasher: BcryptHash,
+    checksum: Vec<u8>,
+}
+
+impl BcryptVerifier {
+    pub fn new(target_hash: &str) -> Result<Self> {
+        let version = match target_hash.get(..4) {
+            Some("$2a$") => Version::TwoA,
+            Some("$2b$") => Version::TwoB,
+            Some("$2y$") => Version::TwoY,
+            Some("$2x$") => {
+                return Err(Error::Unsupported(
+                    "bcrypt '$2x$' hashes (crypt_blowfish's sign extension bug) aren't supported".into(),
+                ));
+            }
+            _ => {
+                return Err(Error::Unsupported(format!(
+                    "Invalid bcrypt format: should start with '$2a$', '$2b$' or '$2y$', got '{}'",
+                    target_hash.get(..4).unwrap_or(target_hash)
+                )));
+            }
+        };
+
+        let parts: HashParts = target_hash
+            .parse()
+            .map_err(|e| Error::Parse(format!("Invalid bcrypt hash '{}': {}", target_hash, e)))?;
+        BcryptHash::check_cost(parts.get_cost())?;
+        let salt = BASE_64
+            .decode(parts.get_salt())
+            .ok()
+            .and_then(|s| <[u8; 16]>::try_from(s).ok())
+            .ok_or_else(|| Error::Parse(format!("Invalid bcrypt salt '{}'", parts.get_salt())))?;
+        // The last field is the 22 char salt followed by the 31 char checksum
+        let field = target_hash.rsplit('$').next().unwrap_or_default();
+        let checksum_b64 = field
+            .get(22..)
+            .filter(|_| field.len() == 53)
+            .ok_or_else(|| Error::Parse(format!("Invalid bcrypt hash '{}': expected 53 chars of salt and checksum", target_hash)))?;
+        let checksum = BASE_64
+            .decode(checksum_b64)
+            .map_err(|e| Error::Parse(format!("Invalid bcrypt checksum '{}': {}", checksum_b64, e)))?;
+
+        Ok(Self {
+            hasher: BcryptHash {
+                version,
+                cost: parts.get_cost(),
+                salt,
+                truncation_warned: AtomicBool::new(false),
+            },
+            checksum,
+        })
     }
 }
 
+impl BcryptHash {
+    /// Fresh `$2b$` hasher with a random salt, used to generate new hashes.
+    pub fn with_cost(cost: u32) -> Result<Self> {
+        Self::check_cost(cost)?;
+        let mut salt = [0u8; 16];
+        salt.copy_from_slice(&random_bytes(16));
+
+        Ok(Self {
+            version: Version::TwoB,
+            cost,
+            salt,
+            truncation_warned: AtomicBool::new(false),
+        })
+    }
+
+    fn check_cost(cost: u32) -> Result<()> {
+        if !(4..=31).contains(&cost) {
+            return Err(Error::Param(format!("Invalid bcrypt cost {}: must be between 4 and 31", cost)));
+        }
+        Ok(())
+    }
+
+    fn warn_truncation(&self, input: &[u8]) {
+        if input.len() > MAX_PASSWORD_LEN && !self.truncation_warned.swap(true, Ordering::Relaxed) {
+            log::warn!(
+                "bcrypt only uses the first {} bytes of a password, longer candidates are truncated",
+                MAX_PASSWORD_LEN
+            );
+        }
+    }
+}
 
 impl HashAlgorithm for BcryptHash {
     fn name(&self) -> &str {
         "BCRYPT"
     }
 
+    /// Raw 23 byte bcrypt output for `input` under this salt and cost.
     fn hash(&self, input: &[u8]) -> Vec<u8> {
-        self.hash_hex(input).into_bytes()
+        self.warn_truncation(input);
+        match hash_with_salt(input, self.cost, self.salt) {
+            Ok(parts) => BASE_64.decode(&parts.to_string()[29..]).unwrap_or_default(),
+            Err(_) => Vec::new(),
+        }
     }
 
-    fn hash_hex(&self, input: &[u8]) -> String {
-        let password = std::str::from_utf8(input).unwrap();
-        if verify(password, &self.target_hash).unwrap_or(false) {
-            self.target_hash.clone()  
-        } else {
-            String::new()
+    fn encode(&self, input: &[u8]) -> String {
+        self.warn_truncation(input);
+        match hash_with_salt(input, self.cost, self.salt) {
+            Ok(parts) => parts.format_for_version(self.version.clone()),
+            Err(_) => String::new(),
         }
     }
-}
\ No newline at end of file
+
+    fn params(&self) -> Option<String> {
+        Some(format!("cost={} ({} rounds)", self.cost, 1u64 << self.cost))
+    }
+}
+
+impl Verifier for BcryptVerifier {
+    fn name(&self) -> &str {
+        self.hasher.name()
+    }
+
+    fn verify(&self, candidate: &[u8]) -> bool {
+        self.hasher.hash(candidate) == self.checksum
+    }
+
+    fn params(&self) -> Option<String> {
+        self.hasher.params()
+    }
+}
@@ -7,10 +7,89 @@ pub mod sha2;
 pub mod sha3;
 pub mod bcrypt;
 pub mod scrypt;
+pub mod phpass;
+use crate::error::{Error, Result};
+
+/// Computes hashes under parameters it owns: the digest role, used to
+/// generate hashes and by verifiers that recompute a target.
 pub trait HashAlgorithm {
      fn name(&self) -> &str;
      fn hash(&self, input: &[u8]) -> Vec<u8>;
      fn hash_hex(&self, input: &[u8]) -> String {
         self.hash(input).iter().map(|b| format!("{:02x}", b)).collect()
     }
-}
\ No newline at end of file
+     /// Canonical text form of the hash of `input`, as it appears in a hash dump.
+     fn encode(&self, input: &[u8]) -> String {
+        self.hash_hex(input)
+    }
+     /// Human readable cost parameters, if any.
+     fn params(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Checks candidates against one target hash, parsed when the verifier is
+/// built: the verifier role.
+pub trait Verifier {
+    fn name(&self) -> &str;
+    fn verify(&self, candidate: &[u8]) -> bool;
+    /// Human readable cost parameters parsed from the target, if any.
+    fn params(&self) -> Option<String> {
+        None
+    }
+    /// For unsalted digests: the hasher and the target's raw digest, so a
+    /// run over many targets can hash each candidate once and look it up.
+    fn digest(&self) -> Option<(&dyn HashAlgorithm, &[u8])> {
+        None
+    }
+}
+
+/// Verifier for unsalted digests and hex scrypt: the target is decoded to
+/// raw bytes once and compared byte for byte with each candidate's digest.
+pub struct DigestVerifier<H> {
+    hasher: H,
+    digest: Vec<u8>,
+}
+
+impl<H: HashAlgorithm> DigestVerifier<H> {
+    /// `expected` is the digest length in bytes; knowing it up front spares
+    /// a derivation for costly hashers such as scrypt.
+    pub fn new(hasher: H, target: &str, expected: usize) -> Result<Self> {
+        let digest = hex::decode(target)
+            .map_err(|e| Error::Parse(format!("Invalid {} hash '{}': {}", hasher.name(), target, e)))?;
+        if digest.len() != expected {
+            return Err(Error::Parse(format!(
+                "Invalid {} hash: expected {} hex chars, got {}",
+                hasher.name(),
+                expected * 2,
+                target.len()
+            )));
+        }
+        Ok(Self { hasher, digest })
+    }
+}
+
+impl<H: HashAlgorithm> Verifier for DigestVerifier<H> {
+    fn name(&self) -> &str {
+        self.hasher.name()
+    }
+
+    fn verify(&self, candidate: &[u8]) -> bool {
+        self.hasher.hash(candidate) == self.digest
+    }
+
+    fn params(&self) -> Option<String> {
+        self.hasher.params()
+    }
+
+    fn digest(&self) -> Option<(&dyn HashAlgorithm, &[u8])> {
+        Some((&self.hasher, &self.digest))
+    }
+}
+
+/// Random bytes from the OS, used for salts of newly generated hashes.
+pub fn random_bytes(len: usize) -> Vec<u8> {
+    let mut buf = vec![0u8; len];
+    getrandom::fill(&mut buf).expect("OS random number generator unavailable");
+    buf
+}
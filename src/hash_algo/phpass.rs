@@ -0,0 +1,148 @@
+use md5;
+use super::{random_bytes, HashAlgorithm, Verifier};
+use crate::error::{Error, Result};
+
+/// Custom base64 alphabet used by phpass ("itoa64").
+const ITOA64: &[u8; 64] = b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Portable phpass hashes as used by WordPress (`$P$`) and phpBB3 (`$H$`):
+/// `$P$<count char><8 byte salt><22 char checksum>`.
+pub struct PhpassHash {
+    prefix: String,
+    count_log2: u32,
+    salt: Vec<u8>,
+}
+
+/// Verifier for a phpass target, holding its itoa64 checksum.
+pub struct PhpassVerifier {
+    hasher: PhpassHash,
+    checksum: String,
+}
+
+impl PhpassVerifier {
+    pub fn new(target: &str) -> Result<Self> {
+        let prefix = target.get(..3).unwrap_or(target);
+        if prefix != "$P$" && prefix != "$H$" {
+            return Err(Error::Unsupported(format!(
+                "Invalid phpass format: should start with '$P$' or '$H$', got '{}'",
+                prefix
+            )));
+        }
+        if target.len() != 34 || !target.is_ascii() {
+            return Err(Error::Parse(format!(
+                "Invalid phpass format: expected 34 chars, got {}",
+                target.len()
+            )));
+        }
+
+        let count_char = target.as_bytes()[3];
+        let count_log2 = ITOA64
+            .iter()
+            .position(|&c| c == count_char)
+            .ok_or_else(|| Error::Parse(format!("Invalid phpass iteration count character '{}'", count_char as char)))?
+            as u32;
+        PhpassHash::check_count(count_log2)?;
+
+        Ok(Self {
+            hasher: PhpassHash {
+                prefix: prefix.to_string(),
+                count_log2,
+                salt: target.as_bytes()[4..12].to_vec(),
+            },
+            checksum: target[12..].to_string(),
+        })
+    }
+}
+
+impl PhpassHash {
+    /// Fresh `$P$` hasher with a random salt and `2^count_log2` iterations,
+    /// used to generate new hashes. WordPress uses 13.
+    pub fn with_count(count_log2: u32) -> Result<Self> {
+        Self::check_count(count_log2)?;
+        let salt = random_bytes(8)
+            .iter()
+            .map(|b| ITOA64[(b & 0x3f) as usize])
+            .collect();
+
+        Ok(Self {
+            prefix: "$P$".to_string(),
+            count_log2,
+            salt,
+        })
+    }
+
+    fn check_count(count_log2: u32) -> Result<()> {
+        if !(7..=30).contains(&count_log2) {
+            return Err(Error::Param(format!(
+                "Invalid phpass iteration count 2^{}: must be between 2^7 and 2^30",
+                count_log2
+            )));
+        }
+        Ok(())
+    }
+
+    /// phpass flavour of base64, least significant bits first.
+    fn encode64(input: &[u8]) -> String {
+        let mut out = String::with_capacity((input.len() * 4).div_ceil(3));
+        for chunk in input.chunks(3) {
+            let mut value = 0u32;
+            for (i, b) in chunk.iter().enumerate() {
+                value |= (*b as u32) << (8 * i);
+            }
+            for i in 0..=chunk.len() {
+                out.push(ITOA64[((value >> (6 * i)) & 0x3f) as usize] as char);
+            }
+        }
+        out
+    }
+}
+
+impl HashAlgorithm for PhpassHash {
+    fn name(&self) -> &str {
+        "PHPASS"
+    }
+
+    fn hash(&self, input: &[u8]) -> Vec<u8> {
+        let mut ctx = md5::Context::new();
+        ctx.consume(&self.salt);
+        ctx.consume(input);
+        let mut digest = ctx.finalize().0;
+
+        for _ in 0..(1u64 << self.count_log2) {
+            let mut ctx = md5::Context::new();
+            ctx.consume(digest);
+            ctx.consume(input);
+            digest = ctx.finalize().0;
+        }
+        digest.to_vec()
+    }
+
+    fn encode(&self, input: &[u8]) -> String {
+        format!(
+            "{}{}{}{}",
+            self.prefix,
+            ITOA64[self.count_log2 as usize] as char,
+            String::from_utf8_lossy(&self.salt),
+            Self::encode64(&self.hash(input))
+        )
+    }
+
+    fn params(&self) -> Option<String> {
+        Some(format!("count=2^{} ({} rounds)", self.count_log2, 1u64 << self.count_log2))
+    }
+}
+
+impl Verifier for PhpassVerifier {
+    fn name(&self) -> &str {
+        self.hasher.name()
+    }
+
+    fn verify(&self, candidate: &[u8]) -> bool {
+        // itoa64 is case sensitive, so compare the encoded checksum exactly
+        PhpassHash::encode64(&self.hasher.hash(candidate)) == self.checksum
+    }
+
+    fn params(&self) -> Option<String> {
+        self.hasher.params()
+    }
+}
@@ -1,134 +1,9 @@
-    // use scrypt::{scrypt, Params};
-    // use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
-    // use hex;
-    // use super::HashAlgorithm;
-
-    // pub struct ScryptHash {
-    //     n: u32,
-    //     r: u32,
-    //     p: u32,
-    //     salt: Vec<u8>,
-    //     key_length: usize,
-    // }
-
-    // impl ScryptHash {
-    //     pub fn new(n: u32, r: u32, p: u32, salt: String, key_length: usize) -> Self {
-    //         assert!(key_length > 0 && key_length <= 1024, "invalid key_length");
-    //         assert!(n >= 2 && (n & (n - 1)) == 0, "n must be a power of two (e.g. 16384)");
-    //         assert!(r > 0 && p > 0, "r and p must be > 0");
-        
-    //         Self {
-    //             n,
-    //             r,
-    //             p,
-    //             salt: salt.into_bytes(),
-    //             key_length,
-    //         }
-    //     }
-        
-
-    //     fn log2_n(&self) -> u8 {
-    //         self.n.trailing_zeros() as u8
-    //     }
-
-    //     fn derive_raw(&self, password: &[u8]) -> Result<Vec<u8>, String> {
-    //         let params = Params::new(self.log2_n(), self.r, self.p, self.key_length)
-    //             .map_err(|e| format!("invalid scrypt params: {:?}", e))?;
-    //         let mut output = vec![0u8; self.key_length];
-    //         scrypt(password, &self.salt, &params, &mut output)
-    //             .map_err(|e| format!("scrypt failed: {:?}", e))?;
-    //         Ok(output)
-    //     }
-
-    //     /// ✅ Industry-style encoded string: `$scrypt$ln=14,r=8,p=1$<salt_b64>$<dk_b64>`
-    //     pub fn encode_password_hash(&self, password: &[u8]) -> Result<String, String> {
-    //         let dk = self.derive_raw(password)?;
-    //         let salt_b64 = B64.encode(&self.salt);
-    //         let dk_b64 = B64.encode(&dk);
-
-    //         Ok(format!(
-    //             "$scrypt$ln={},r={},p={}${}${}",
-    //             self.log2_n(),
-    //             self.r,
-    //             self.p,
-    //             salt_b64,
-    //             dk_b64
-    //         ))
-    //     }
-
-    //     /// ✅ Verification: re-derive and compare encoded hash
-    //     pub fn verify_encoded(encoded: &str, candidate: &[u8]) -> Result<bool, String> {
-    //         let parts: Vec<&str> = encoded.split('$').collect();
-    //         if parts.len() != 5 || parts[1] != "scrypt" {
-    //             return Err("invalid scrypt encoded format".into());
-    //         }
-
-    //         // parse ln, r, p
-    //         let params_str = parts[2];
-    //         let mut ln = None;
-    //         let mut r = None;
-    //         let mut p = None;
-
-    //         for kv in params_str.split(',') {
-    //             let mut split = kv.split('=');
-    //             match (split.next(), split.next()) {
-    //                 (Some("ln"), Some(v)) => ln = v.parse::<u8>().ok(),
-    //                 (Some("r"), Some(v)) => r = v.parse::<u32>().ok(),
-    //                 (Some("p"), Some(v)) => p = v.parse::<u32>().ok(),
-    //                 _ => {}
-    //             }
-    //         }
-
-    //         let ln = ln.ok_or("missing ln")?;
-    //         let r = r.ok_or("missing r")?;
-    //         let p = p.ok_or("missing p")?;
-    //         let n = 1u32 << ln;
-
-    //         let salt = B64.decode(parts[3].as_bytes()).map_err(|_| "invalid base64 salt")?;
-    //         let dk_expected = B64.decode(parts[4].as_bytes()).map_err(|_| "invalid base64 dk")?;
-    //         let key_length = dk_expected.len();
-
-    //         let hasher = Self {
-    //             n,
-    //             r,
-    //             p,
-    //             salt,
-    //             key_length,
-    //         };
-
-    //         let dk_actual = hasher.derive_raw(candidate)?;
-    //         Ok(dk_actual == dk_expected)
-    //     }
-    // }
-
-    // impl HashAlgorithm for ScryptHash {
-    //     fn name(&self) -> &str {
-    //         "SCRYPT"
-    //     }
-
-    //     fn hash(&self, input: &[u8]) -> Vec<u8> {
-    //         self.derive_raw(input).unwrap_or_default()
-    //     }
-
-    //     fn hash_hex(&self, input: &[u8]) -> String {
-    //         match self.derive_raw(input) {
-    //             Ok(out) => hex::encode(out),
-    //             Err(_) => String::new(),
-    //         }
-    //     }
-    // }
-
-
-
-
-
-
-// Improved scrypt.rs with better error handling and debugging
-
 use scrypt::{scrypt, Params};
 use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
 use hex;
-use super::HashAlgorithm;
+use super::{HashAlgorithm, Verifier};
+use crate::error::{Error, Result};
+use crate::logging::Secret;
 
 pub struct ScryptHash {
     n: u32,
@@ -139,35 +14,46 @@ pub struct ScryptHash {
 }
 
 impl ScryptHash {
-    pub fn new(n: u32, r: u32, p: u32, salt: String, key_length: usize) -> Self {
-        assert!(key_length > 0 && key_length <= 1024, "invalid key_length");
-        assert!(n >= 2 && (n & (n - 1)) == 0, "n must be a power of two (e.g. 16384)");
-        assert!(r > 0 && p > 0, "r and p must be > 0");
+    pub fn new(n: u32, r: u32, p: u32, salt: String, key_length: usize) -> Result<Self> {
+        Self::with_salt(n, r, p, salt.into_bytes(), key_length)
+    }
+
+    /// Like [`ScryptHash::new`], with the salt as raw bytes.
+    pub fn with_salt(n: u32, r: u32, p: u32, salt: Vec<u8>, key_length: usize) -> Result<Self> {
+        if key_length == 0 || key_length > 1024 {
+            return Err(Error::Param(format!("Invalid scrypt key_length {}: must be 1 to 1024", key_length)));
+        }
+        if n < 2 || !n.is_power_of_two() {
+            return Err(Error::Param(format!("Invalid scrypt n {}: must be a power of two (e.g. 16384)", n)));
+        }
+        if r == 0 || p == 0 {
+            return Err(Error::Param("Invalid scrypt parameters: r and p must be > 0".into()));
+        }
     
-        Self {
+        Ok(Self {
             n,
             r,
             p,
-            salt: salt.into_bytes(),
+            salt,
             key_length,
-        }
+        })
     }
     
     fn log2_n(&self) -> u8 {
         self.n.trailing_zeros() as u8
     }
 
-    fn derive_raw(&self, password: &[u8]) -> Result<Vec<u8>, String> {
+    fn derive_raw(&self, password: &[u8]) -> Result<Vec<u8>> {
         let params = Params::new(self.log2_n(), self.r, self.p, self.key_length)
-            .map_err(|e| format!("invalid scrypt params: {:?}", e))?;
+            .map_err(|e| Error::Param(format!("Invalid scrypt parameters: {}", e)))?;
         let mut output = vec![0u8; self.key_length];
         scrypt(password, &self.salt, &params, &mut output)
-            .map_err(|e| format!("scrypt failed: {:?}", e))?;
+            .map_err(|e| Error::Param(format!("scrypt failed: {}", e)))?;
         Ok(output)
     }
 
     /// Industry-style encoded string: `$scrypt$ln=14,r=8,p=1$<salt_b64>$<dk_b64>`
-    pub fn _encode_password_hash(&self, password: &[u8]) -> Result<String, String> {
+    pub fn encode_password_hash(&self, password: &[u8]) -> Result<String> {
         let dk = self.derive_raw(password)?;
         let salt_b64 = B64.encode(&self.salt);
         let dk_b64 = B64.encode(&dk);
@@ -182,26 +68,43 @@ impl ScryptHash {
         ))
     }
 
-    /// Improved verification with better error messages
-    pub fn verify_encoded(encoded: &str, candidate: &[u8]) -> Result<bool, String> {
-        // Debug: Print what we're verifying
-        eprintln!("DEBUG: Verifying encoded hash: {}", encoded);
+    /// Alternative method to verify a hex-encoded hash (if needed)
+    pub fn _verify_hex(&self, hex_hash: &str, candidate: &[u8]) -> Result<bool> {
+        let expected = hex::decode(hex_hash)
+            .map_err(|e| Error::Parse(format!("Invalid hex hash: {}", e)))?;
+        let actual = self.derive_raw(candidate)?;
+        Ok(actual == expected)
+    }
+}
+
+/// Verifier for encoded scrypt, which carries its own parameters: the
+/// industry-standard `$scrypt$ln=14,r=8,p=1$<salt_b64>$<dk_b64>` or hashcat's
+/// `SCRYPT:<n>:<r>:<p>:<salt_b64>:<dk_b64>`.
+pub struct ScryptVerifier {
+    hasher: ScryptHash,
+    expected: Vec<u8>,
+}
+
+impl ScryptVerifier {
+    /// Parses the encoded hash, with better error messages
+    pub fn new(encoded: &str) -> Result<Self> {
+        log::trace!("Parsing encoded scrypt hash {}", Secret(encoded));
         
         let parts: Vec<&str> = encoded.split('$').collect();
         
         // Better error message
         if parts.len() != 5 {
-            return Err(format!(
+            return Err(Error::Parse(format!(
                 "Invalid scrypt format: expected 5 parts, got {}. Format should be: $scrypt$params$salt$hash",
                 parts.len()
-            ));
+            )));
         }
         
-        if parts[0] != "" || parts[1] != "scrypt" {
-            return Err(format!(
+        if !parts[0].is_empty() || parts[1] != "scrypt" {
+            return Err(Error::Parse(format!(
                 "Invalid scrypt format: should start with '$scrypt$', got '${}'",
                 parts.get(1).unwrap_or(&"")
-            ));
+            )));
         }
 
         // Parse parameters with better error reporting
@@ -223,55 +126,83 @@ impl ScryptHash {
                     p = v.parse::<u32>().map_err(|e| format!("Invalid p value '{}': {}", v, e)).ok();
                 }
                 _ => {
-                    eprintln!("WARNING: Unknown parameter in scrypt hash: {}", kv);
+                    log::warn!("Unknown parameter in scrypt hash: {}", kv);
                 }
             }
         }
 
-        let ln = ln.ok_or_else(|| format!("Missing 'ln' parameter in: {}", params_str))?;
-        let r = r.ok_or_else(|| format!("Missing 'r' parameter in: {}", params_str))?;
-        let p = p.ok_or_else(|| format!("Missing 'p' parameter in: {}", params_str))?;
-        let n = 1u32 << ln;
+        let ln = ln.ok_or_else(|| Error::Parse(format!("Missing 'ln' parameter in: {}", params_str)))?;
+        let r = r.ok_or_else(|| Error::Parse(format!("Missing 'r' parameter in: {}", params_str)))?;
+        let p = p.ok_or_else(|| Error::Parse(format!("Missing 'p' parameter in: {}", params_str)))?;
+        let n = 1u32
+            .checked_shl(ln.into())
+            .ok_or_else(|| Error::Param(format!("Invalid scrypt ln {}: must be below 32", ln)))?;
 
-        // Debug: Print parsed parameters
-        eprintln!("DEBUG: Parsed params - n:{} (ln:{}), r:{}, p:{}", n, ln, r, p);
+        log::trace!("Parsed scrypt params: n={} (ln={}), r={}, p={}", n, ln, r, p);
 
         let salt = B64.decode(parts[3])
-            .map_err(|e| format!("Invalid base64 salt '{}': {}", parts[3], e))?;
+            .map_err(|e| Error::Parse(format!("Invalid base64 salt '{}': {}", parts[3], e)))?;
         let dk_expected = B64.decode(parts[4])
-            .map_err(|e| format!("Invalid base64 hash '{}': {}", parts[4], e))?;
+            .map_err(|e| Error::Parse(format!("Invalid base64 hash '{}': {}", parts[4], e)))?;
         let key_length = dk_expected.len();
 
-        eprintln!("DEBUG: Salt length: {}, Expected hash length: {}", salt.len(), key_length);
+        log::trace!("Salt length: {}, expected key length: {}", salt.len(), key_length);
 
-        let hasher = Self {
-            n,
-            r,
-            p,
-            salt,
-            key_length,
+        Self::with_params(n, r, p, salt, dk_expected)
+    }
+
+    /// Parses hashcat's mode 8900 form, `SCRYPT:16384:8:1:<salt_b64>:<dk_b64>`.
+    pub fn from_hashcat(hash: &str) -> Result<Self> {
+        let fields: Vec<&str> = hash.split(':').collect();
+        let ["SCRYPT", n, r, p, salt, dk] = fields[..] else {
+            return Err(Error::Parse(
+                "Invalid hashcat scrypt format: should be SCRYPT:N:r:p:salt:hash".into(),
+            ));
+        };
+        let number = |name: &str, v: &str| {
+            v.parse::<u32>()
+                .map_err(|e| Error::Parse(format!("Invalid {} value '{}': {}", name, v, e)))
         };
+        let salt = B64.decode(salt)
+            .map_err(|e| Error::Parse(format!("Invalid base64 salt '{}': {}", salt, e)))?;
+        let dk_expected = B64.decode(dk)
+            .map_err(|e| Error::Parse(format!("Invalid base64 hash '{}': {}", dk, e)))?;
+        Self::with_params(number("N", n)?, number("r", r)?, number("p", p)?, salt, dk_expected)
+    }
 
-        let dk_actual = hasher.derive_raw(candidate)?;
-        
-        // Debug: Compare hashes
-        if dk_actual == dk_expected {
-            eprintln!("DEBUG: Password match!");
-            Ok(true)
-        } else {
-            eprintln!("DEBUG: Password mismatch");
-            eprintln!("  Expected: {}", hex::encode(&dk_expected));
-            eprintln!("  Got:      {}", hex::encode(&dk_actual));
-            Ok(false)
-        }
+    fn with_params(n: u32, r: u32, p: u32, salt: Vec<u8>, expected: Vec<u8>) -> Result<Self> {
+        Ok(Self {
+            hasher: ScryptHash::with_salt(n, r, p, salt, expected.len())?,
+            expected,
+        })
     }
-    
-    /// Alternative method to verify a hex-encoded hash (if needed)
-    pub fn _verify_hex(&self, hex_hash: &str, candidate: &[u8]) -> Result<bool, String> {
-        let expected = hex::decode(hex_hash)
-            .map_err(|e| format!("Invalid hex hash: {}", e))?;
-        let actual = self.derive_raw(candidate)?;
-        Ok(actual == expected)
+}
+
+impl Verifier for ScryptVerifier {
+    fn name(&self) -> &str {
+        self.hasher.name()
+    }
+
+    fn verify(&self, candidate: &[u8]) -> bool {
+        let dk_actual = match self.hasher.derive_raw(candidate) {
+            Ok(dk) => dk,
+            Err(e) => {
+                log::error!("scrypt hash failed: {}", e);
+                return false;
+            }
+        };
+        let matched = dk_actual == self.expected;
+        log::trace!(
+            "scrypt {}: expected {}, got {}",
+            if matched { "match" } else { "mismatch" },
+            Secret(hex::encode(&self.expected)),
+            Secret(hex::encode(&dk_actual))
+        );
+        matched
+    }
+
+    fn params(&self) -> Option<String> {
+        self.hasher.params()
     }
 }
 
@@ -288,42 +219,30 @@ impl HashAlgorithm for ScryptHash {
         match self.derive_raw(input) {
             Ok(out) => hex::encode(out),
             Err(e) => {
-                eprintln!("ERROR in hash_hex: {}", e);
+                log::error!("scrypt hash failed: {}", e);
                 String::new()
             }
         }
     }
-}
 
-// Test helper function
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
+    fn params(&self) -> Option<String> {
+        Some(format!(
+            "n={} (ln={}), r={}, p={}, key_length={}",
+            self.n,
+            self.log2_n(),
+            self.r,
+            self.p,
+            self.key_length
+        ))
+    }
 
-//     #[test]
-//     fn test_encode_decode_roundtrip() {
-//         let hasher = ScryptHash::new(16384, 8, 1, "test_salt".to_string(), 32);
-//         let password = b"test_password";
-        
-//         let encoded = hasher.encode_password_hash(password).unwrap();
-//         println!("Encoded: {}", encoded);
-        
-//         let verified = ScryptHash::verify_encoded(&encoded, password).unwrap();
-//         assert!(verified, "Password should verify correctly");
-        
-//         let wrong_verified = ScryptHash::verify_encoded(&encoded, b"wrong_password").unwrap();
-//         assert!(!wrong_verified, "Wrong password should not verify");
-//     }
-    
-//     #[test]
-//     fn test_verify_known_hash() {
-//         // Example with a known hash (you'd replace with your actual test case)
-//         let encoded = "$scrypt$ln=14,r=8,p=1$dGVzdF9zYWx0$KL6k4LDuojE1aGou+Mq8QXFzP1HFDPqjDdL3VwqKWAo=";
-//         let password = b"test_password";
-        
-//         match ScryptHash::verify_encoded(encoded, password) {
-//             Ok(verified) => println!("Verification result: {}", verified),
-//             Err(e) => println!("Verification error: {}", e),
-//         }
-//     }
-// }
\ No newline at end of file
+    fn encode(&self, input: &[u8]) -> String {
+        match self.encode_password_hash(input) {
+            Ok(encoded) => encoded,
+            Err(e) => {
+                log::error!("scrypt encode failed: {}", e);
+                String::new()
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Hybrid attacks: every string of a mask appended to or prepended to each
+//! wordlist word, through the dictionary attack's expansion hook.
+
+use crate::crack::Expand;
+use crate::mask::Mask;
+
+/// Which side of the word the mask goes on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Side {
+    /// `word + mask`, hashcat's `-a 6`
+    Append,
+    /// `mask + word`, hashcat's `-a 7`
+    Prepend,
+}
+
+/// Expands each word into one candidate per string of the mask.
+pub struct Hybrid {
+    mask: Mask,
+    side: Side,
+}
+
+impl Hybrid {
+    pub fn new(mask: Mask, side: Side) -> Self {
+        Self { mask, side }
+    }
+}
+
+impl Expand for Hybrid {
+    fn count(&self) -> u64 {
+        self.mask.keyspace()
+    }
+
+    fn apply(&self, word: &[u8], n: u64, out: &mut Vec<u8>) {
+        match self.side {
+            Side::Append => {
+                out.extend_from_slice(word);
+                self.mask.write(n, out);
+            }
+            Side::Prepend => {
+                self.mask.write(n, out);
+                out.extend_from_slice(word);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+    use super::*;
+    use crate::crack::{Cracker, Events, Position};
+    use crate::registry::{create_verifier, HashMode};
+    use crate::wordlist::Source;
+
+    struct Tried(Vec<String>);
+
+    impl Events for Tried {
+        fn candidate(&mut self, word: &[u8], _offset: u64) {
+            self.0.push(String::from_utf8_lossy(word).into_owned());
+        }
+    }
+
+    fn hybrid(mask: &str, side: Side) -> Hybrid {
+        Hybrid::new(mask.parse().unwrap(), side)
+    }
+
+    fn apply(hybrid: &Hybrid, word: &str, n: u64) -> String {
+        let mut out = Vec::new();
+        hybrid.apply(word.as_bytes(), n, &mut out);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn mask_goes_on_its_side() {
+        let append = hybrid("?d?d", Side::Append);
+        let prepend = hybrid("?d?d", Side::Prepend);
+        assert_eq!(append.count(), 100);
+        assert_eq!(prepend.count(), 100);
+        assert_eq!(apply(&append, "pass", 0), "pass00");
+        assert_eq!(apply(&append, "pass", 7), "pass07");
+        assert_eq!(apply(&prepend, "pass", 99), "99pass");
+        assert_eq!(apply(&hybrid("19?d?d", Side::Prepend), "summer", 84), "1984summer");
+    }
+
+    #[test]
+    fn slices_run_across_word_boundaries() {
+        let path = env::temp_dir().join(format!("hash_forge_hybrid_{}.txt", process::id()));
+        fs::write(&path, "cat\ndog\n").unwrap();
+        let sources = [Source::File(path.clone())];
+        let verifier = create_verifier(&HashMode::MD5, &"0".repeat(32), None).unwrap();
+
+        let cases = [
+            (Side::Append, ["cat98", "cat99", "dog00", "dog01"]),
+            (Side::Prepend, ["98cat", "99cat", "00dog", "01dog"]),
+        ];
+        for (side, expected) in cases {
+            let hybrid = hybrid("?d?d", side);
+            let mut cracker = Cracker::new(vec![verifier.as_ref()]);
+            cracker.expand = Some(&hybrid);
+            cracker.skip = 98;
+            cracker.limit = Some(4);
+            let mut tried = Tried(Vec::new());
+            let report = cracker.run(&sources, &Position::default(), &mut tried);
+            assert_eq!(tried.0, expected);
+            assert_eq!(report.tried, 4);
+        }
+        fs::remove_file(path).unwrap();
+    }
+}
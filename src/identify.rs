@@ -0,0 +1,42 @@
+use std::io;
+use hash_forge::error::{Error, Result};
+use hash_forge::identify;
+use hash_forge::wordlist::read_line;
+use crate::IdentifyArgs;
+
+/// Prints the modes each hash could be, one hash per `--hash` or stdin
+/// line. Returns whether every hash matched at least one mode.
+pub fn run(args: &IdentifyArgs) -> Result<bool> {
+    let mut recognized = true;
+    let mut report = |hash: &str| {
+        let hash = hash.trim();
+        if hash.is_empty() {
+            return;
+        }
+        let modes = identify(hash);
+        if modes.is_empty() {
+            println!("{}: unknown", hash);
+            recognized = false;
+            return;
+        }
+        println!("{}:", hash);
+        for mode in modes {
+            match mode.hashcat {
+                Some(number) => println!("  {:<8} {} (hashcat {})", mode.name, mode.description, number),
+                None => println!("  {:<8} {}", mode.name, mode.description),
+            }
+        }
+    };
+
+    if !args.hash.is_empty() {
+        args.hash.iter().for_each(|hash| report(hash));
+        return Ok(recognized);
+    }
+
+    let mut stdin = io::stdin().lock();
+    let mut line = Vec::new();
+    while read_line(&mut stdin, &mut line).map_err(Error::io("Error reading stdin"))? > 0 {
+        report(&String::from_utf8_lossy(&line));
+    }
+    Ok(recognized)
+}
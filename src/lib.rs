@@ -0,0 +1,32 @@
+//! Hash generation, verification and dictionary attacks, as used by the
+//! `hash_forge` command line tool.
+//!
+//! ```no_run
+//! use hash_forge::{create_verifier, wordlist, Cracker, HashMode, Position};
+//!
+//! let verifier = create_verifier(&HashMode::MD5, "5f4dcc3b5aa765d61d8327deb882cf99", None)?;
+//! let sources = wordlist::expand_sources(&["words.txt".into()])?;
+//! let report = Cracker::new(vec![verifier.as_ref()]).run(&sources, &Position::default(), &mut ());
+//! println!("{:?}", report.outcome);
+//! # Ok::<(), hash_forge::Error>(())
+//! ```
+
+pub mod combinator;
+pub mod crack;
+pub mod encoding;
+pub mod error;
+pub mod hash_algo;
+pub mod hybrid;
+pub mod logging;
+pub mod mask;
+pub mod potfile;
+pub mod registry;
+pub mod rules;
+pub mod single;
+pub mod targets;
+pub mod wordlist;
+
+pub use crack::{Control, Cracker, Events, Expand, Hit, Outcome, Position, Report};
+pub use error::{Error, Result};
+pub use hash_algo::{HashAlgorithm, Verifier};
+pub use registry::{create_generator, create_verifier, identify, Algorithm, HashMode, ScryptParams};
@@ -0,0 +1,116 @@
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, IsTerminal, Write},
+    path::Path,
+    time::SystemTime,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use crate::error::{Error, Result};
+
+static SHOW_SECRETS: AtomicBool = AtomicBool::new(false);
+
+/// Diagnostics on stderr, or appended to `--log-file` instead. Stderr lines
+/// first erase an in-place status line so the two don't run together.
+struct Logger {
+    file: Option<Mutex<File>>,
+    inline: bool,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        match &self.file {
+            Some(file) => {
+                let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+                let _ = writeln!(
+                    file,
+                    "{} {:<5} {}: {}",
+                    timestamp(),
+                    record.level(),
+                    record.target(),
+                    record.args()
+                );
+            }
+            None => {
+                let mut stderr = io::stderr().lock();
+                if self.inline {
+                    let _ = write!(stderr, "\r\x1b[2K");
+                }
+                let _ = match record.level() {
+                    Level::Info => writeln!(stderr, "{}", record.args()),
+                    level => writeln!(stderr, "{}: {}", level, record.args()),
+                };
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            let _ = file.lock().unwrap_or_else(|e| e.into_inner()).flush();
+        }
+    }
+}
+
+/// Level from the `-v`/`-q` counts: info by default, `-v` adds debug and
+/// `-vv` trace, `-q` keeps errors only and `-qq` silences logging entirely.
+pub fn level(verbose: u8, quiet: u8) -> LevelFilter {
+    match (verbose, quiet) {
+        (_, 2..) => LevelFilter::Off,
+        (_, 1) => LevelFilter::Error,
+        (0, _) => LevelFilter::Info,
+        (1, _) => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+pub fn init(level: LevelFilter, log_file: Option<&Path>, show_secrets: bool) -> Result<()> {
+    let file = match log_file {
+        Some(path) => Some(Mutex::new(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(Error::io(format!("Error opening log file {}", path.display())))?,
+        )),
+        None => None,
+    };
+    SHOW_SECRETS.store(show_secrets, Ordering::Relaxed);
+    let logger = Logger {
+        file,
+        inline: io::stderr().is_terminal(),
+    };
+    // Only fails if a logger is already set, i.e. on a second call
+    let _ = log::set_boxed_logger(Box::new(logger));
+    log::set_max_level(level);
+    Ok(())
+}
+
+/// Wraps hash material (digests, derived keys, salts, cracked plaintexts) so
+/// it only reaches the logs with `--debug-secrets`.
+pub struct Secret<T>(pub T);
+
+impl<T: fmt::Display> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if SHOW_SECRETS.load(Ordering::Relaxed) {
+            self.0.fmt(f)
+        } else {
+            write!(f, "<redacted>")
+        }
+    }
+}
+
+/// Current time as RFC 3339 in UTC, to the second.
+pub fn timestamp() -> String {
+    humantime::format_rfc3339_seconds(SystemTime::now()).to_string()
+}
@@ -1,209 +1,746 @@
-mod hash_algo;
-use clap::{Parser, Subcommand};
-use std::{
-    io::{self, BufRead},
-    path::PathBuf,
-    process,
-};
-use hash_algo::bcrypt::BcryptHash;
-use hash_algo::scrypt::ScryptHash;
-use hash_algo::{
-    HashAlgorithm,
-    md2::Md2Hash,
-    md4::Md4Hash,
-    md5::Md5Hash,
-    md6::Md6Hash,
-    sha1::Sha1Hash,
-    sha2::Sha2Hash,
-    sha3::Sha3Hash,
+mod benchmark;
+mod distributed;
+mod generate;
+mod identify;
+mod modes;
+mod output;
+mod session;
+mod status;
+mod verify;
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
+use std::{collections::HashSet, path::PathBuf, process, time::Duration};
+use hash_forge::encoding::{Charset, Normalization, Transcoder};
+use hash_forge::error::{exit, Error, Result};
+use hash_forge::logging::Secret;
+use hash_forge::combinator::Combinator;
+use hash_forge::hybrid::{Hybrid, Side};
+use hash_forge::mask::Mask;
+use hash_forge::rules::Rule;
+use hash_forge::single;
+use hash_forge::targets::{Format, Target, TargetList};
+use hash_forge::wordlist::{self, Compression, Source};
+use hash_forge::{
+    create_verifier, logging, Control, Cracker, Events, Expand, HashMode, Hit, Outcome, Position,
+    ScryptParams, Verifier,
 };
+use output::{CrackEvent, Output, OutputFormat, Summary};
+use session::{Checkpoint, Cracked, Session};
+use status::Status;
 
 #[derive(Parser)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, subcommand_negates_reqs = true, after_help = exit::HELP)]
 struct Cli {
-    #[arg(short = 'f', long, value_name = "FILE", required = true)]
-    file: PathBuf,
+    /// Wordlist file or directory (walked recursively), `-` for stdin; repeat to chain several
+    #[arg(short = 'f', long, value_name = "FILE", required_unless_present_any = ["restore", "single"])]
+    file: Vec<PathBuf>,
     
-    #[arg(long, value_name = "HASH", required = true)]
-    hash: String,
+    #[arg(long, value_name = "HASH", required_unless_present_any = ["restore", "keyspace", "hash_file"])]
+    hash: Option<String>,
+
+    /// Hashes to crack, one per line: shadow, passwd, pwdump, NTDS secretsdump, htpasswd,
+    /// user:hash:salt or bare hashes
+    #[arg(long, value_name = "FILE", conflicts_with = "hash")]
+    hash_file: Option<PathBuf>,
+
+    /// Single crack mode: try each target's user name, full name and home directory,
+    /// mangled by built-in rules, against that target alone
+    #[arg(long, conflicts_with = "file")]
+    single: bool,
+
+    /// Combinator attack: join every --file word with every word of FILE; repeat to chain several
+    #[arg(long, value_name = "FILE", conflicts_with = "single")]
+    right: Vec<PathBuf>,
+
+    /// Text put between the left and right words; repeat to try several
+    #[arg(long, value_name = "SEP", requires = "right")]
+    separator: Vec<String>,
+
+    /// Rule applied to every left word, e.g. `c` to capitalize; repeat to try several
+    #[arg(long, value_name = "RULE", requires = "right")]
+    rule_left: Vec<Rule>,
+
+    /// Rule applied to every right word, e.g. `$1` to append a 1; repeat to try several
+    #[arg(long, value_name = "RULE", requires = "right")]
+    rule_right: Vec<Rule>,
+
+    /// Hybrid attack: append every string of MASK to each --file word, e.g. `?d?d?d?d`
+    /// (?l ?u ?d ?h ?H ?s ?a ?b, ?? for a literal ?)
+    #[arg(long, value_name = "MASK", conflicts_with_all = ["single", "right", "prepend_mask"])]
+    append_mask: Option<Mask>,
+
+    /// Hybrid attack: put every string of MASK in front of each --file word
+    #[arg(long, value_name = "MASK", conflicts_with_all = ["single", "right"])]
+    prepend_mask: Option<Mask>,
+
+    /// Strip leading and trailing whitespace from every wordlist line
+    #[arg(long)]
+    trim: bool,
+
+    /// More log output: -v debug, printing every candidate instead of the status line; -vv trace
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Less output: -q errors only and no status line, -qq no logging at all
+    #[arg(short, long, action = clap::ArgAction::Count, global = true, conflicts_with = "verbose")]
+    quiet: u8,
+
+    /// Append log records to FILE instead of printing them on stderr
+    #[arg(long, value_name = "FILE", global = true)]
+    log_file: Option<PathBuf>,
+
+    /// Show hash material and cracked plaintexts in log records instead of redacting them
+    #[arg(long, global = true)]
+    debug_secrets: bool,
+
+    #[command(flatten)]
+    encoding: EncodingArgs,
     
-    #[arg(short, long, value_name = "MODE", required = true)]
-    mode: HashMode,
+    /// Hash mode, by name, hashcat -m number or John --format name (see list-modes)
+    #[arg(short, long, value_name = "MODE", ignore_case = true, required_unless_present_any = ["restore", "keyspace"])]
+    mode: Option<HashMode>,
+
+    /// Print the number of candidates in the wordlists and exit
+    #[arg(long)]
+    keyspace: bool,
+
+    /// Pass over the first N candidates of the keyspace
+    #[arg(long, value_name = "N", default_value = "0")]
+    skip: u64,
+
+    /// Stop after processing M candidates (counted after --skip)
+    #[arg(long, value_name = "M")]
+    limit: Option<u64>,
+
+    /// Session name; progress is checkpointed to NAME.session
+    #[arg(long, value_name = "NAME", default_value = "hash_forge")]
+    session: String,
+
+    /// Continue the run saved in a session (name or .session file)
+    #[arg(long, value_name = "SESSION", conflicts_with_all = ["file", "hash", "hash_file", "single", "right", "append_mask", "prepend_mask", "mode"])]
+    restore: Option<String>,
+
+    /// Seconds between session checkpoints
+    #[arg(long, value_name = "SECS", default_value = "60")]
+    checkpoint_interval: u64,
+
+    /// Format of results and status records
+    #[arg(long, value_enum, value_name = "FORMAT", default_value = "text")]
+    output_format: OutputFormat,
+
+    /// Write results to FILE instead of stdout
+    #[arg(long, value_name = "FILE")]
+    outfile: Option<PathBuf>,
     
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+impl Cli {
+    fn scrypt_params(&self) -> Option<&ScryptParams> {
+        match &self.command {
+            Some(Command::Scrypt(params)) => Some(params.params()),
+            _ => None,
+        }
+    }
+}
+
+// Re-encoding applied to every candidate before it is hashed. Not a doc
+// comment: clap would take it for the about text of every command that
+// flattens these arguments.
+#[derive(clap::Args, Debug)]
+struct EncodingArgs {
+    /// Encoding the candidates are written in (utf-8, utf-16le/be, latin1, iso-8859-N, cp1252)
+    #[arg(long, value_name = "ENCODING", default_value = "utf-8")]
+    encoding_from: Charset,
+
+    /// Encoding the target was hashed over, e.g. utf-16le for Windows hashes
+    #[arg(long, value_name = "ENCODING", default_value = "utf-8")]
+    encoding_to: Charset,
+
+    /// Unicode normalization applied before re-encoding
+    #[arg(long, value_name = "FORM")]
+    normalize: Option<Normalization>,
+}
+
+impl EncodingArgs {
+    fn transcoder(&self) -> Transcoder {
+        Transcoder::new(self.encoding_from, self.encoding_to, self.normalize)
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    #[command(flatten)]
+    Scrypt(ScryptCommand),
+    /// Generate hashes for a plaintext, or for every line read from stdin
+    Hash(HashArgs),
+    /// Check one password against a hash; exits 0 on match, 1 on mismatch, 2 on error
+    Verify(VerifyArgs),
+    /// List the modes a hash could be, from its format and length
+    Identify(IdentifyArgs),
+    /// Print every mode with its hashcat number and John format name
+    ListModes(ListModesArgs),
+    /// Measure hashes per second for each mode and thread count
+    Benchmark(BenchmarkArgs),
+    /// Split a crack job into chunks and hand them to workers over TCP
+    Server(ServerArgs),
+    /// Connect to a server and work on the chunks it hands out
+    Worker(WorkerArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct HashArgs {
+    #[arg(short, long, value_name = "MODE", ignore_case = true)]
+    mode: HashMode,
+
+    /// Plaintext to hash; reads one plaintext per line from stdin when omitted
+    #[arg(short, long, value_name = "PASSWORD")]
+    password: Option<String>,
+
+    /// bcrypt cost factor (default 12), phpass log2 iteration count (default 13)
+    /// or scrypt log2 N (overrides the scrypt subcommand's --n)
+    #[arg(long, value_name = "COST")]
+    cost: Option<u32>,
+
+    #[command(flatten)]
+    encoding: EncodingArgs,
+
     #[command(subcommand)]
     scrypt: Option<ScryptCommand>,
 }
 
 #[derive(Subcommand, Debug)]
 enum ScryptCommand {
-    Scrypt {
-        #[arg(long, default_value = "16384")]
-        n: u32,
-        #[arg(long, default_value = "8")]
-        r: u32,
-        #[arg(long, default_value = "1")]
-        p: u32,
-        #[arg(long, default_value = "salty_salty")]
-        salt: String,
-        #[arg(long, default_value = "32")]
-        key_length: usize,
-    }
-}
-
-#[derive(clap::ValueEnum, Clone, Debug)]
-enum HashMode {
-    Md2,
-    Md4,
-    Md5,
-    Md6,
-    Sha1,
-    Sha2,
-    Sha3,
-    Bcrypt,
-    Scrypt,
-}
-
-fn word_comp(cli: &Cli, hasher: Option<Box<dyn HashAlgorithm>>) {
-    let file = match std::fs::File::open(&cli.file) {
-        Ok(f) => f,
-        Err(e) => {
-            eprintln!("Error reading file {}: {}", cli.file.display(), e);
-            return;
-        }
-    };
+    Scrypt(ScryptParams),
+}
+
+impl ScryptCommand {
+    fn params(&self) -> &ScryptParams {
+        let ScryptCommand::Scrypt(params) = self;
+        params
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct VerifyArgs {
+    #[arg(long, value_name = "HASH")]
+    hash: String,
+
+    #[arg(short, long, value_name = "MODE", ignore_case = true)]
+    mode: HashMode,
+
+    /// Password to check; read from the first stdin line when omitted
+    #[arg(short, long, value_name = "PASSWORD")]
+    password: Option<String>,
+
+    #[command(flatten)]
+    encoding: EncodingArgs,
+
+    #[command(subcommand)]
+    scrypt: Option<ScryptCommand>,
+}
+
+#[derive(clap::Args, Debug)]
+struct IdentifyArgs {
+    /// Hash to identify; repeat for several, reads one per line from stdin when omitted
+    #[arg(value_name = "HASH")]
+    hash: Vec<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct ListModesArgs {
+    /// Also print an example hash for every mode
+    #[arg(long)]
+    examples: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct BenchmarkArgs {
+    /// Modes to benchmark; repeat for several, all modes when omitted
+    #[arg(short, long, value_name = "MODE", ignore_case = true)]
+    mode: Vec<HashMode>,
 
-    let reader = io::BufReader::new(file);
-    let target_hash = cli.hash.trim(); // Trim any whitespace from input hash
-
-    for (i, line) in reader.lines().enumerate() {
-        if let Ok(word) = line {
-            let word = word.trim(); // Trim whitespace from word
-            println!("Trying: {}", word);
-
-            let matched = match cli.mode {
-                HashMode::Scrypt => {
-                    // For scrypt, check if it's encoded format or hex format
-                    if target_hash.starts_with("$scrypt$") {
-                        // Industry-standard encoded format
-                        match ScryptHash::verify_encoded(target_hash, word.as_bytes()) {
-                            Ok(result) => result,
-                            Err(e) => {
-                                eprintln!("Error verifying scrypt hash: {}", e);
-                                false
-                            }
-                        }
-                    } else {
-                        // Hex format - use the hasher instance
-                        if let Some(ref h) = hasher {
-                            let computed_hash = h.hash_hex(word.as_bytes());
-                            computed_hash.eq_ignore_ascii_case(target_hash)
-                        } else {
-                            eprintln!("No hasher available for hex scrypt comparison");
-                            false
-                        }
-                    }
-                }
-                HashMode::Bcrypt => {
-                    // BCrypt typically uses encoded format like $2b$...
-                    if target_hash.starts_with("$2") {
-                        // Use bcrypt's verify method if available
-                        // For now, fall back to hasher
-                        if let Some(ref h) = hasher {
-                            let computed_hash = h.hash_hex(word.as_bytes());
-                            computed_hash.eq_ignore_ascii_case(target_hash)
-                        } else {
-                            false
-                        }
-                    } else {
-                        if let Some(ref h) = hasher {
-                            let computed_hash = h.hash_hex(word.as_bytes());
-                            computed_hash.eq_ignore_ascii_case(target_hash)
-                        } else {
-                            false
-                        }
-                    }
-                }
-                _ => {
-                    // For other hashes, just compare raw hex
-                    if let Some(ref h) = hasher {
-                        let computed_hash = h.hash_hex(word.as_bytes());
-                        computed_hash.eq_ignore_ascii_case(target_hash)
-                    } else {
-                        eprintln!("No hasher available");
-                        false
-                    }
-                }
-            };
-
-            if matched {
-                println!("✅ Match found at line {}: {}", i + 1, word);
-                println!("Hash: {}", target_hash);
-                return;
+    /// Seconds to run each mode at each thread count
+    #[arg(long, value_name = "SECS", default_value = "2")]
+    duration: f64,
+
+    /// Comma separated thread counts, defaults to 1 and all cores
+    #[arg(long, value_name = "N,...", value_delimiter = ',')]
+    threads: Option<Vec<usize>>,
+
+    /// bcrypt cost factor (default 12)
+    #[arg(long, value_name = "COST")]
+    bcrypt_cost: Option<u32>,
+
+    /// phpass log2 iteration count (default 13)
+    #[arg(long, value_name = "COUNT")]
+    phpass_count: Option<u32>,
+
+    #[arg(long, value_enum, default_value = "table")]
+    format: BenchmarkFormat,
+
+    #[command(subcommand)]
+    scrypt: Option<ScryptCommand>,
+}
+
+#[derive(clap::Args, Debug)]
+struct ServerArgs {
+    /// Address to accept workers on
+    #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:7878")]
+    listen: String,
+
+    /// Candidates per chunk handed to a worker
+    #[arg(long, value_name = "N", default_value = "100000")]
+    chunk_size: u64,
+
+    /// Where cracked hashes are recorded as hash:plaintext
+    #[arg(long, value_name = "FILE", default_value = "hash_forge.potfile")]
+    potfile: PathBuf,
+
+    /// Seconds without a message before a worker is considered dead
+    #[arg(long, value_name = "SECS", default_value = "30")]
+    timeout: u64,
+
+    /// Crack arguments for the job, e.g. `-- -f words.txt --hash H -m md5`;
+    /// workers need the wordlists at the same paths
+    #[arg(last = true, required = true, value_name = "JOB")]
+    job: Vec<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct WorkerArgs {
+    /// Server address
+    #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:7878")]
+    connect: String,
+
+    /// Name shown in the server's log, defaults to the process id
+    #[arg(long, value_name = "NAME")]
+    name: Option<String>,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum BenchmarkFormat {
+    Table,
+    Json,
+}
+
+/// Shows a crack run on the terminal: the status line, results in the
+/// chosen output format, and session checkpoints.
+struct RunEvents<'a> {
+    cli: &'a Cli,
+    mode: &'a HashMode,
+    /// The targets handed to the cracker, in its order
+    targets: Vec<&'a Target>,
+    /// Everything cracked so far, including by the session being restored
+    found: Vec<Cracked>,
+    source_count: usize,
+    status: Status,
+    output: &'a mut Output,
+    checkpoint: &'a Checkpoint,
+    candidates_logged: bool,
+}
+
+impl Events for RunEvents<'_> {
+    fn poll(&mut self, position: &Position) -> Control {
+        let interrupted = session::interrupted();
+        if interrupted || self.checkpoint.is_due() {
+            match self.checkpoint.save(position, &self.found) {
+                Ok(()) => log::debug!("Checkpoint saved to {}", self.checkpoint.path().display()),
+                Err(e) => log::error!("{}", e),
+            }
+            if interrupted {
+                return Control::Stop;
             }
         }
+        Control::Continue
     }
 
-    println!("❌ No match found.");
-}
-
-fn create_hasher(cli: &Cli) -> Result<Option<Box<dyn HashAlgorithm>>, String> {
-    match cli.mode {
-        HashMode::Md2 => Ok(Some(Box::new(Md2Hash))),
-        HashMode::Md4 => Ok(Some(Box::new(Md4Hash))),
-        HashMode::Md5 => Ok(Some(Box::new(Md5Hash))),
-        HashMode::Md6 => Ok(Some(Box::new(Md6Hash))),
-        HashMode::Sha1 => Ok(Some(Box::new(Sha1Hash))),
-        HashMode::Sha2 => Ok(Some(Box::new(Sha2Hash))),
-        HashMode::Sha3 => Ok(Some(Box::new(Sha3Hash))),
-        HashMode::Bcrypt => Ok(Some(Box::new(BcryptHash::new(cli.hash.clone())))),
-        HashMode::Scrypt => {
-            // Only create hasher if we're using hex format
-            if cli.hash.starts_with("$scrypt$") {
-                // For encoded format, we don't need a hasher instance
-                Ok(None)
-            } else {
-                // For hex format, we need a hasher with parameters
-                match &cli.scrypt {
-                    Some(ScryptCommand::Scrypt { n, r, p, salt, key_length }) => {
-                        // ScryptHash::new doesn't return Result, adjust if needed
-                        let hasher = ScryptHash::new(*n, *r, *p, salt.clone(), *key_length);
-                        Ok(Some(Box::new(hasher)))
-                    }
-                    None => {
-                        // If no parameters provided, we can't create a hasher for hex format
-                        Err("Scrypt parameters required for hex format verification".into())
-                    }
-                }
-            }
+    fn source_started(&mut self, index: usize, source: &Source, compression: Compression) {
+        self.status.clear();
+        let (n, count) = (index + 1, self.source_count);
+        match compression {
+            Compression::None => log::info!("Source {}/{}: {}", n, count, source),
+            _ => log::info!("Source {}/{}: {} ({})", n, count, source, compression),
+        }
+    }
+
+    fn resumed(&mut self, source: &Source, line: u64, offset: u64) {
+        self.status.resume_source(offset);
+        log::info!("Resuming {} at line {}", source, line);
+    }
+
+    fn candidate(&mut self, word: &[u8], offset: u64) {
+        if self.candidates_logged {
+            log::debug!("Trying: {}", wordlist::display_candidate(word));
+        }
+        self.status.candidate(offset);
+        if let Some(snapshot) = self.status.take_snapshot() {
+            self.output.status(&snapshot);
+        }
+    }
+
+    fn cracked(&mut self, hit: &Hit) {
+        let target = self.targets[hit.target];
+        self.status.cracked();
+        self.status.clear();
+        let event = CrackEvent::new(
+            target,
+            hit.plaintext,
+            self.mode.to_string(),
+            hit.line,
+            hit.source.map(Source::to_string),
+        );
+        self.output.cracked(&event);
+        self.found.push(Cracked { hash: target.hash.clone(), plaintext_hex: event.plaintext_hex });
+    }
+
+    fn source_finished(&mut self, source: &Source, tried: u64, skipped: u64) {
+        self.status.source_done();
+        self.status.clear();
+        log::info!("Finished {}: {} candidates tried", source, tried);
+        if skipped > 0 {
+            let transcoder = self.cli.encoding.transcoder();
+            log::info!("Skipped {} candidates not convertible {}", skipped, transcoder);
         }
     }
+
+    fn limit_reached(&mut self, index: u64) {
+        log::info!("Reached --limit at candidate {}", index);
+    }
+
+    fn error(&mut self, error: &Error) {
+        log::error!("{}", error);
+    }
 }
 
-fn main() {
-    let cli = Cli::parse();
-    
-    // Validate input
-    if cli.hash.is_empty() {
-        eprintln!("Error: Hash cannot be empty");
-        process::exit(1);
+/// Cracks the targets not in `found` yet, which holds those a restored
+/// session already cracked.
+#[allow(clippy::too_many_arguments)]
+fn word_comp(
+    cli: &Cli,
+    sources: &[Source],
+    mode: &HashMode,
+    targets: &[(Target, Box<dyn Verifier>)],
+    expand: Option<&dyn Expand>,
+    found: Vec<Cracked>,
+    resume: &Position,
+    checkpoint: &Checkpoint,
+    output: &mut Output,
+) -> Outcome {
+    let done: HashSet<&str> = found.iter().map(|cracked| cracked.hash.as_str()).collect();
+    let restored = targets.iter().filter(|(target, _)| done.contains(target.hash.as_str())).count();
+    if restored > 0 {
+        log::info!("Restored {} cracked targets from the session", restored);
     }
-    
-    // Create hasher (may be None for encoded scrypt)
-    let hasher = match create_hasher(&cli) {
-        Ok(h) => h,
+    // Single mode positions count targets, so the cracker gets all of them
+    let (pending, verifiers): (Vec<&Target>, Vec<&dyn Verifier>) = targets
+        .iter()
+        .filter(|(target, _)| cli.single || !done.contains(target.hash.as_str()))
+        .map(|(target, verifier)| (target, verifier.as_ref()))
+        .unzip();
+    let single: Vec<Option<Vec<Vec<u8>>>> = match cli.single {
+        true => pending
+            .iter()
+            .map(|target| (!done.contains(target.hash.as_str())).then(|| single::candidates(target)))
+            .collect(),
+        false => Vec::new(),
+    };
+    if cli.single {
+        let count: usize = single.iter().flatten().map(Vec::len).sum();
+        log::info!("Single mode: {} candidates for {} targets", count, targets.len() - restored);
+    }
+
+    let total_bytes = match cli.single {
+        true => None,
+        false => sources.iter().map(Source::plain_size).sum::<Option<u64>>(),
+    };
+    let records = cli.output_format != OutputFormat::Text;
+    let candidates_logged = log::log_enabled!(log::Level::Debug);
+    let draw = !candidates_logged && cli.quiet == 0;
+    let mut status = Status::new(total_bytes, targets.len(), draw, records);
+    for _ in 0..restored {
+        status.cracked();
+    }
+    let bytes_before = sources[..resume.source.min(sources.len())]
+        .iter()
+        .map(Source::plain_size)
+        .sum::<Option<u64>>();
+    status.resume(resume.tried, bytes_before.unwrap_or(0));
+
+    let mut cracker = Cracker::new(verifiers);
+    cracker.expand = expand;
+    cracker.transcoder = cli.encoding.transcoder();
+    cracker.trim = cli.trim;
+    cracker.skip = cli.skip;
+    cracker.limit = cli.limit;
+    let mut events = RunEvents {
+        cli,
+        mode,
+        targets: pending,
+        found,
+        source_count: sources.len(),
+        status,
+        output,
+        checkpoint,
+        candidates_logged,
+    };
+    let outcome = match cli.single {
+        true => cracker.run_single(&single, resume, &mut events).outcome,
+        false => cracker.run(sources, resume, &mut events).outcome,
+    };
+
+    let RunEvents { mut status, output, found, .. } = events;
+    match outcome {
+        Outcome::Aborted => {
+            status.finish();
+            log::info!(
+                "Interrupted, session saved to {}. Continue with --restore {}",
+                checkpoint.path().display(),
+                cli.session
+            );
+        }
+        Outcome::Exhausted | Outcome::Cracked => {
+            status.finish();
+            checkpoint.remove();
+        }
+    }
+    finish(output, &mut status, outcome, found.len(), targets.len())
+}
+
+/// Writes the run's summary record and passes its outcome on.
+fn finish(output: &mut Output, status: &mut Status, outcome: Outcome, cracked: usize, targets: usize) -> Outcome {
+    if let Some(snapshot) = status.take_snapshot() {
+        output.status(&snapshot);
+    }
+    output.summary(&Summary {
+        outcome,
+        tried: status.tried(),
+        cracked,
+        targets,
+        elapsed_secs: status.elapsed().as_secs_f64(),
+        timestamp: logging::timestamp(),
+    });
+    outcome
+}
+
+fn main() {
+    let code = match run(std::env::args().collect()) {
+        Ok(code) => code,
         Err(e) => {
             eprintln!("Error: {}", e);
-            process::exit(1);
+            exit::ERROR
         }
     };
-    
+    process::exit(code);
+}
+
+/// Runs the command line and returns the process exit code.
+fn run(mut args: Vec<String>) -> Result<i32> {
+    let mut cli = Cli::parse_from(&args);
+    logging::init(
+        logging::level(cli.verbose, cli.quiet),
+        cli.log_file.as_deref(),
+        cli.debug_secrets,
+    )?;
+    let mut resume = Position::default();
+    let mut found = Vec::new();
+    if let Some(name) = &cli.restore {
+        let session = Session::load(name)?;
+        args = session.args;
+        cli = Cli::parse_from(&args);
+        resume = session.position;
+        found = session.cracked;
+    }
+
+    match &cli.command {
+        Some(Command::Hash(args)) => return generate::run(args).map(|_| exit::SUCCESS),
+        Some(Command::Benchmark(args)) => return benchmark::run(args).map(|_| exit::SUCCESS),
+        Some(Command::Worker(args)) => return distributed::worker::run(args).map(|_| exit::SUCCESS),
+        Some(Command::Server(args)) => {
+            let cracked = distributed::server::run(args)?;
+            return Ok(if cracked { exit::CRACKED } else { exit::EXHAUSTED });
+        }
+        Some(Command::Verify(args)) => {
+            let matched = verify::run(args)?;
+            return Ok(if matched { exit::CRACKED } else { exit::EXHAUSTED });
+        }
+        Some(Command::ListModes(args)) => {
+            modes::run(args);
+            return Ok(exit::SUCCESS);
+        }
+        Some(Command::Identify(args)) => {
+            let recognized = identify::run(args)?;
+            return Ok(if recognized { exit::SUCCESS } else { exit::EXHAUSTED });
+        }
+        Some(Command::Scrypt(_)) | None => {}
+    }
+
+    if cli.keyspace {
+        println!("{}", keyspace(&cli)?);
+        return Ok(exit::SUCCESS);
+    }
+
+    let (false, true, Some(mode)) = (
+        cli.file.is_empty() && !cli.single,
+        cli.hash.is_some() || cli.hash_file.is_some(),
+        &cli.mode,
+    ) else {
+        Cli::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "--file or --single, --hash or --hash-file, and --mode are required when cracking",
+            )
+            .exit();
+    };
+    let sources = match cli.single {
+        true => Vec::new(),
+        false => wordlist::expand_sources(&cli.file)?,
+    };
+    let expand = expansion(&cli)?;
+    let targets = load_targets(&cli, mode)?;
+    let mut output = Output::new(cli.output_format, cli.outfile.as_deref())?;
+
     // Print mode information
-    println!("Mode: {:?}", cli.mode);
-    println!("Hash format: {}", 
-        if cli.hash.starts_with("$") { "encoded" } else { "hex" }
-    );
-    println!("Hash length: {} chars", cli.hash.len());
-    println!("---");
+    let (first, verifier) = &targets[0];
+    log::info!("Mode: {}", mode);
+    log::info!("Algorithm: {}", verifier.name());
+    let params = verifier.params();
+    if let Some(params) = &params
+        && targets.iter().all(|(_, verifier)| verifier.params().as_ref() == Some(params))
+    {
+        log::info!("Parameters: {}", params);
+    }
+    match &cli.hash_file {
+        Some(path) => log::info!("Targets: {} from {}", targets.len(), path.display()),
+        None => {
+            let target_hash = &first.hash;
+            log::info!("Hash format: {}",
+                if target_hash.starts_with("$") { "encoded" } else { "hex" }
+            );
+            log::info!("Hash length: {} chars", target_hash.len());
+        }
+    }
+    if cli.skip > 0 || cli.limit.is_some() {
+        match cli.limit {
+            Some(limit) => log::info!("Slice: skip {}, limit {}", cli.skip, limit),
+            None => log::info!("Slice: skip {}", cli.skip),
+        }
+    }
+    if let Some(expand) = &expand {
+        let attack = match (&cli.append_mask, &cli.prepend_mask) {
+            (Some(mask), _) => format!("Hybrid: word + {}", mask),
+            (_, Some(mask)) => format!("Hybrid: {} + word", mask),
+            _ => "Combinator".to_string(),
+        };
+        log::info!("{}: {} candidates per --file word", attack, expand.count());
+    }
+    let transcoder = cli.encoding.transcoder();
+    if !transcoder.is_identity() {
+        log::info!("Encoding: {}", transcoder);
+    }
+    log::info!("---");
     
-    word_comp(&cli, hasher);
-}
\ No newline at end of file
+    if let Err(e) = session::install_interrupt_handler() {
+        log::warn!("{}", e);
+    }
+    let checkpoint = Checkpoint::new(
+        &cli.session,
+        args.clone(),
+        Duration::from_secs(cli.checkpoint_interval.max(1)),
+    );
+
+    let outcome = word_comp(
+        &cli,
+        &sources,
+        mode,
+        &targets,
+        expand.as_deref(),
+        found,
+        &resume,
+        &checkpoint,
+        &mut output,
+    );
+    Ok(outcome.exit_code())
+}
+
+/// Candidates a run would go through: the lines of the wordlists, or in
+/// single mode what the target file's accounts give.
+fn keyspace(cli: &Cli) -> Result<u64> {
+    if !cli.single {
+        let lines = wordlist::keyspace(&wordlist::expand_sources(&cli.file)?)?;
+        return Ok(lines.saturating_mul(expansion(cli)?.map_or(1, |expand| expand.count())));
+    }
+    let Some(path) = &cli.hash_file else {
+        return Err(Error::Param("--keyspace with --single needs a --hash-file".into()));
+    };
+    // With a mode, only the targets a run would load count
+    let targets = match &cli.mode {
+        Some(mode) => load_targets(cli, mode)?.into_iter().map(|(target, _)| target).collect(),
+        None => TargetList::load(path)?.targets,
+    };
+    Ok(targets.iter().map(|target| single::candidates(target).len() as u64).sum())
+}
+
+/// How a combinator or hybrid attack expands each --file word, if this is one.
+fn expansion(cli: &Cli) -> Result<Option<Box<dyn Expand>>> {
+    if let Some(mask) = &cli.append_mask {
+        return Ok(Some(Box::new(Hybrid::new(mask.clone(), Side::Append))));
+    }
+    if let Some(mask) = &cli.prepend_mask {
+        return Ok(Some(Box::new(Hybrid::new(mask.clone(), Side::Prepend))));
+    }
+    if cli.right.is_empty() {
+        return Ok(None);
+    }
+    let right = wordlist::read_words(&wordlist::expand_sources(&cli.right)?, cli.trim)?;
+    if right.is_empty() {
+        return Err(Error::Param("No words in the --right wordlists".into()));
+    }
+    let separators = cli.separator.iter().map(|separator| separator.as_bytes().to_vec()).collect();
+    let combinator = Combinator::new(&right, cli.rule_left.clone(), &cli.rule_right, separators);
+    Ok(Some(Box::new(combinator)))
+}
+
+/// The targets of a crack run with their verifiers: the `--hash`, or every
+/// usable line of the `--hash-file`. Lines that aren't are logged with
+/// their line numbers.
+fn load_targets(cli: &Cli, mode: &HashMode) -> Result<Vec<(Target, Box<dyn Verifier>)>> {
+    let Some(path) = &cli.hash_file else {
+        let hash = cli.hash.as_deref().unwrap_or_default().trim(); // Trim any whitespace from input hash
+        if hash.is_empty() {
+            return Err(Error::Parse("Hash cannot be empty".into()));
+        }
+        let verifier = create_verifier(mode, hash, cli.scrypt_params())?;
+        return Ok(vec![(Target::bare(hash), verifier)]);
+    };
+
+    let list = TargetList::load(path)?;
+    for rejected in &list.malformed {
+        log::warn!("{}:{}: malformed line: {}", path.display(), rejected.line, rejected.reason);
+    }
+    for rejected in &list.skipped {
+        log::info!("{}:{}: skipped: {}", path.display(), rejected.line, rejected.reason);
+    }
+    let mut targets = Vec::new();
+    for target in list.targets {
+        match create_verifier(mode, &target.hash, cli.scrypt_params()) {
+            Ok(verifier) => targets.push((target, verifier)),
+            Err(e) => {
+                log::warn!("{}:{}: not a valid {} hash ({} line)", path.display(), target.line, mode, target.format);
+                log::debug!("{}", Secret(e));
+            }
+        }
+    }
+    if targets.is_empty() {
+        return Err(Error::Parse(format!("No {} hashes in {}", mode, path.display())));
+    }
+
+    if targets.iter().any(|(target, _)| target.salt.is_some()) {
+        log::warn!("{} reads any salt from the hash itself; salt fields are ignored", mode);
+    }
+    let windows = targets.iter().any(|(target, _)| matches!(target.format, Format::Pwdump | Format::Ntds));
+    if windows && (*mode != HashMode::MD4 || cli.encoding.encoding_to != Charset::Utf16Le) {
+        log::warn!("pwdump and NTDS lines hold NT hashes, cracked with -m md4 --encoding-to utf-16le");
+    }
+    Ok(targets)
+}
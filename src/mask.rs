@@ -0,0 +1,148 @@
+//! Masks in hashcat's syntax: a charset per position, e.g. `?u?l?l?d?d`
+//! for an upper case letter, two lower case ones and two digits.
+
+use std::{fmt, str::FromStr};
+use crate::error::{Error, Result};
+
+const LOWER: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const UPPER: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &[u8] = b"0123456789";
+const SPECIAL: &[u8] = b" !\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+/// Every string a mask stands for, numbered from 0 with the last position
+/// changing fastest.
+#[derive(Clone, Debug)]
+pub struct Mask {
+    positions: Vec<Vec<u8>>,
+    keyspace: u64,
+    text: String,
+}
+
+impl Mask {
+    /// Number of strings the mask stands for.
+    pub fn keyspace(&self) -> u64 {
+        self.keyspace
+    }
+
+    /// Appends string `n`, below `keyspace()`, to `out`.
+    pub fn write(&self, mut n: u64, out: &mut Vec<u8>) {
+        let start = out.len();
+        out.resize(start + self.positions.len(), 0);
+        for (slot, charset) in out[start..].iter_mut().zip(&self.positions).rev() {
+            let len = charset.len() as u64;
+            *slot = charset[(n % len) as usize];
+            n /= len;
+        }
+    }
+}
+
+impl FromStr for Mask {
+    type Err = Error;
+
+    /// `?l` a-z, `?u` A-Z, `?d` 0-9, `?h` 0-9a-f, `?H` 0-9A-F, `?s` symbols
+    /// and space, `?a` all of `?l?u?d?s`, `?b` any byte, `??` a literal `?`;
+    /// anything else stands for itself.
+    fn from_str(text: &str) -> Result<Self> {
+        let invalid = |why: String| Error::Parse(format!("Invalid mask '{}': {}", text, why));
+        let mut positions = Vec::new();
+        let mut bytes = text.bytes();
+        while let Some(b) = bytes.next() {
+            if b != b'?' {
+                positions.push(vec![b]);
+                continue;
+            }
+            let charset = match bytes.next() {
+                Some(b'l') => LOWER.to_vec(),
+                Some(b'u') => UPPER.to_vec(),
+                Some(b'd') => DIGITS.to_vec(),
+                Some(b'h') => b"0123456789abcdef".to_vec(),
+                Some(b'H') => b"0123456789ABCDEF".to_vec(),
+                Some(b's') => SPECIAL.to_vec(),
+                Some(b'a') => [LOWER, UPPER, DIGITS, SPECIAL].concat(),
+                Some(b'b') => (0..=255).collect(),
+                Some(b'?') => vec![b'?'],
+                Some(other) => return Err(invalid(format!("unknown charset '?{}'", other as char))),
+                None => return Err(invalid("ends in a lone '?'".into())),
+            };
+            positions.push(charset);
+        }
+        if positions.is_empty() {
+            return Err(invalid("empty".into()));
+        }
+        let keyspace = positions
+            .iter()
+            .try_fold(1u64, |total, charset| total.checked_mul(charset.len() as u64))
+            .ok_or_else(|| invalid("more than 2^64 candidates".into()))?;
+        Ok(Self { positions, keyspace, text: text.to_string() })
+    }
+}
+
+impl fmt::Display for Mask {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(mask: &str) -> Vec<Vec<u8>> {
+        let mask: Mask = mask.parse().unwrap();
+        (0..mask.keyspace())
+            .map(|n| {
+                let mut out = Vec::new();
+                mask.write(n, &mut out);
+                out
+            })
+            .collect()
+    }
+
+    fn strings(mask: &str) -> Vec<String> {
+        bytes(mask).into_iter().map(|out| String::from_utf8(out).unwrap()).collect()
+    }
+
+    #[test]
+    fn two_digits_count_up_from_00() {
+        let all = strings("?d?d");
+        assert_eq!(all.len(), 100);
+        assert_eq!(all[..3], ["00", "01", "02"]);
+        assert_eq!(all[42], "42");
+        assert_eq!(all[99], "99");
+    }
+
+    #[test]
+    fn charsets_have_their_sizes() {
+        let sizes = [("?l", 26), ("?u", 26), ("?d", 10), ("?h", 16), ("?H", 16), ("?s", 33), ("?a", 95), ("?b", 256)];
+        for (mask, size) in sizes {
+            let all = bytes(mask);
+            assert_eq!(all.len(), size, "{}", mask);
+            let mut unique = all.clone();
+            unique.sort();
+            unique.dedup();
+            assert_eq!(unique.len(), size, "{} repeats a character", mask);
+        }
+        assert_eq!(strings("?h").concat(), "0123456789abcdef");
+        assert_eq!(strings("?H").concat(), "0123456789ABCDEF");
+        assert!(strings("?a").iter().all(|s| s.bytes().all(|b| b.is_ascii_graphic() || b == b' ')));
+    }
+
+    #[test]
+    fn literals_stand_for_themselves() {
+        assert_eq!(strings("19?d?d")[..2], ["1900", "1901"]);
+        assert_eq!(strings("??"), ["?"]);
+        assert_eq!(strings("x?uy"), (b'A'..=b'Z').map(|c| format!("x{}y", c as char)).collect::<Vec<_>>());
+        // The last position changes fastest
+        assert_eq!(strings("?l?d")[..12].concat(), "a0a1a2a3a4a5a6a7a8a9b0b1");
+    }
+
+    #[test]
+    fn bad_masks_are_rejected() {
+        for mask in ["", "?", "ab?", "?x", "?d?D", &"?b".repeat(9)] {
+            assert!(mask.parse::<Mask>().is_err(), "'{}' parses", mask);
+        }
+        let mask: Mask = "?u?l?l?d".parse().unwrap();
+        assert_eq!(mask.to_string(), "?u?l?l?d");
+        assert_eq!(mask.keyspace(), 26 * 26 * 26 * 10);
+    }
+}
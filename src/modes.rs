@@ -0,0 +1,31 @@
+use hash_forge::registry::{ALGORITHMS, EXAMPLE_PLAINTEXT};
+use crate::ListModesArgs;
+
+/// Prints every mode with its hashcat number, John format name and
+/// properties, and optionally an example hash of each.
+pub fn run(args: &ListModesArgs) {
+    let yes_no = |flag: bool| if flag { "yes" } else { "no" };
+    println!(
+        "{:<8} {:>7}  {:<11} {:<7} {:<9} Aliases",
+        "Mode", "Hashcat", "John", "Salted", "Iterated"
+    );
+    for mode in ALGORITHMS {
+        let hashcat = mode.hashcat.map_or("-".to_string(), |number| number.to_string());
+        let aliases = if mode.aliases.is_empty() { "-".to_string() } else { mode.aliases.join(", ") };
+        println!(
+            "{:<8} {:>7}  {:<11} {:<7} {:<9} {}",
+            mode.name,
+            hashcat,
+            mode.john.unwrap_or("-"),
+            yes_no(mode.salted),
+            yes_no(mode.iterated),
+            aliases
+        );
+        if args.examples {
+            println!("         {}", mode.example);
+        }
+    }
+    if args.examples {
+        println!("\nExamples are hashes of '{}'", EXAMPLE_PLAINTEXT);
+    }
+}
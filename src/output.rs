@@ -0,0 +1,169 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+use serde::Serialize;
+use hash_forge::crack::Outcome;
+use hash_forge::error::{Error, Result};
+use hash_forge::logging::timestamp;
+use hash_forge::targets::Target;
+use hash_forge::wordlist::display_candidate;
+use crate::status::Snapshot;
+
+/// How results are written.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    /// Human readable lines
+    Text,
+    /// One JSON document with every record, written when the run ends
+    Json,
+    /// One JSON record per line as events happen
+    Jsonl,
+}
+
+/// A successful crack.
+#[derive(Serialize, Debug)]
+pub struct CrackEvent {
+    pub hash: String,
+    /// Account the hash belongs to, when the target file names one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Plaintext as UTF-8, or `$HEX[...]` when it isn't clean text
+    pub plaintext: String,
+    pub plaintext_hex: String,
+    pub mode: String,
+    /// Line of `source`, or the candidate's number for its target in single mode
+    pub line: u64,
+    /// Wordlist the plaintext came from, absent in single mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    pub timestamp: String,
+}
+
+impl CrackEvent {
+    pub fn new(target: &Target, plaintext: &[u8], mode: String, line: u64, source: Option<String>) -> Self {
+        Self {
+            hash: target.hash.clone(),
+            user: target.user.clone(),
+            plaintext: display_candidate(plaintext).into_owned(),
+            plaintext_hex: hex::encode(plaintext),
+            mode,
+            line,
+            source,
+            timestamp: timestamp(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct Summary {
+    pub outcome: Outcome,
+    pub tried: u64,
+    pub cracked: usize,
+    pub targets: usize,
+    pub elapsed_secs: f64,
+    pub timestamp: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Record<'a> {
+    Cracked(&'a CrackEvent),
+    Status(&'a Snapshot),
+    Summary(&'a Summary),
+}
+
+/// Result writer for a crack run: stdout or `--outfile`, in the chosen format.
+pub struct Output {
+    format: OutputFormat,
+    writer: Box<dyn Write>,
+    /// Encoded records held back for the single `json` document
+    records: Vec<String>,
+}
+
+impl Output {
+    pub fn new(format: OutputFormat, outfile: Option<&Path>) -> Result<Self> {
+        let writer: Box<dyn Write> = match outfile {
+            Some(path) => Box::new(BufWriter::new(
+                File::create(path).map_err(Error::io(format!("Error creating output file {}", path.display())))?,
+            )),
+            None => Box::new(io::stdout()),
+        };
+        Ok(Self {
+            format,
+            writer,
+            records: Vec::new(),
+        })
+    }
+
+    pub fn cracked(&mut self, event: &CrackEvent) {
+        match self.format {
+            OutputFormat::Text => {
+                let found = match &event.source {
+                    Some(source) => format!("at line {} of {}", event.line, source),
+                    None => "in single mode".to_string(),
+                };
+                let mut text = format!("✅ Match found {}: {}\nHash: {}", found, event.plaintext, event.hash);
+                if let Some(user) = &event.user {
+                    text.push_str(&format!("\nUser: {}", user));
+                }
+                self.write_line(&text);
+            }
+            _ => self.record(Record::Cracked(event)),
+        }
+    }
+
+    /// Periodic status; text mode already shows it on the status line.
+    pub fn status(&mut self, snapshot: &Snapshot) {
+        if self.format != OutputFormat::Text {
+            self.record(Record::Status(snapshot));
+        }
+    }
+
+    /// Ends the run, writing the `json` document if that format was chosen.
+    pub fn summary(&mut self, summary: &Summary) {
+        match self.format {
+            OutputFormat::Text => match summary.outcome {
+                Outcome::Exhausted if summary.targets > 1 => {
+                    let line = format!("❌ Cracked {} of {} targets.", summary.cracked, summary.targets);
+                    self.write_line(&line);
+                }
+                Outcome::Exhausted => self.write_line("❌ No match found."),
+                Outcome::Cracked if summary.targets > 1 => {
+                    self.write_line(&format!("✅ All {} targets cracked.", summary.targets));
+                }
+                _ => {}
+            },
+            OutputFormat::Jsonl => self.record(Record::Summary(summary)),
+            OutputFormat::Json => {
+                self.record(Record::Summary(summary));
+                // An array with one record per line, keeping each record's field order
+                let document = format!("[\n  {}\n]", self.records.join(",\n  "));
+                self.write_line(&document);
+            }
+        }
+        if let Err(e) = self.writer.flush() {
+            log::error!("Error writing output: {}", e);
+        }
+    }
+
+    fn record(&mut self, record: Record) {
+        let encoded = match serde_json::to_string(&record) {
+            Ok(encoded) => encoded,
+            Err(e) => return log::error!("Error encoding output: {}", e),
+        };
+        if self.format == OutputFormat::Jsonl {
+            self.write_line(&encoded);
+            let _ = self.writer.flush();
+        } else {
+            self.records.push(encoded);
+        }
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if let Err(e) = writeln!(self.writer, "{}", line) {
+            log::error!("Error writing output: {}", e);
+        }
+    }
+}
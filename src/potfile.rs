@@ -0,0 +1,53 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+use crate::error::{Error, Result};
+use crate::wordlist::{candidate, display_candidate};
+
+/// Cracked hashes, one `hash:plaintext` line each, the same layout hashcat
+/// uses. Plaintexts that aren't clean UTF-8 are stored as `$HEX[...]`.
+pub struct Potfile {
+    path: PathBuf,
+}
+
+impl Potfile {
+    pub fn new(path: &Path) -> Self {
+        Self { path: path.to_path_buf() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Plaintext recorded for `hash`, if any; a missing potfile is simply
+    /// empty.
+    pub fn find(&self, hash: &str) -> Result<Option<Vec<u8>>> {
+        let data = match fs::read(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(source) => {
+                let context = format!("Error reading potfile {}", self.path.display());
+                return Err(Error::Io { context, source });
+            }
+        };
+        // Both hashes (hashcat's SCRYPT:N:r:p:...) and plaintexts may contain
+        // ':', so match the whole hash and its separator at the line start
+        let prefix = format!("{}:", hash);
+        Ok(data
+            .split(|&b| b == b'\n')
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+            .find_map(|line| line.strip_prefix(prefix.as_bytes()))
+            .map(|plaintext| candidate(plaintext, false).into_owned()))
+    }
+
+    pub fn append(&self, hash: &str, plaintext: &[u8]) -> Result<()> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| writeln!(file, "{}:{}", hash, display_candidate(plaintext)))
+            .map_err(Error::io(format!("Error writing potfile {}", self.path.display())))
+    }
+}
@@ -0,0 +1,510 @@
+use std::{fmt, ops::Deref};
+use clap::{builder::PossibleValue, ValueEnum};
+use bcrypt::DEFAULT_COST;
+use crate::error::{Error, Result};
+use crate::hash_algo::{
+    DigestVerifier,
+    HashAlgorithm,
+    Verifier,
+    bcrypt::{BcryptHash, BcryptVerifier},
+    md2::Md2Hash,
+    md4::Md4Hash,
+    md5::Md5Hash,
+    md6::Md6Hash,
+    phpass::{PhpassHash, PhpassVerifier},
+    random_bytes,
+    scrypt::{ScryptHash, ScryptVerifier},
+    sha1::Sha1Hash,
+    sha2::Sha2Hash,
+    sha3::Sha3Hash,
+};
+
+/// phpass iteration count (log2) used by WordPress.
+const DEFAULT_PHPASS_COUNT: u32 = 13;
+
+/// Plaintext every [`Algorithm::example`] is a hash of.
+pub const EXAMPLE_PLAINTEXT: &str = "password";
+
+/// Builds the verifier for a target hash.
+pub type Parser = fn(&str, Option<&ScryptParams>) -> Result<Box<dyn Verifier>>;
+
+/// Builds a hasher for new hashes with a fresh salt and the given cost.
+pub type Generator = fn(Option<u32>, Option<&ScryptParams>) -> Result<Box<dyn HashAlgorithm>>;
+
+/// Everything hash_forge knows about one algorithm. `--mode`, `identify`
+/// and the help output are all built from [`ALGORITHMS`], so adding a mode
+/// means adding an entry there.
+pub struct Algorithm {
+    /// `--mode` value
+    pub name: &'static str,
+    /// Other accepted `--mode` values
+    pub aliases: &'static [&'static str],
+    /// hashcat's `-m` number for the same hash type, if it has one; also
+    /// accepted by `--mode`
+    pub hashcat: Option<u32>,
+    /// John the Ripper's `--format=` name, if it has one; also accepted by
+    /// `--mode`
+    pub john: Option<&'static str>,
+    pub description: &'static str,
+    /// Hash of [`EXAMPLE_PLAINTEXT`], in the form targets are given in
+    pub example: &'static str,
+    /// Raw hash length in bytes; for scrypt, that of the default key length
+    pub output_len: usize,
+    pub salted: bool,
+    /// Whether the cost can be tuned with a work factor or iteration count
+    pub iterated: bool,
+    pub parse: Parser,
+    pub generate: Generator,
+}
+
+/// Parameters for hex scrypt targets, which unlike `$scrypt$` hashes don't
+/// carry their own.
+#[derive(clap::Args, Clone, Debug)]
+pub struct ScryptParams {
+    #[arg(long, default_value = "16384")]
+    pub n: u32,
+    #[arg(long, default_value = "8")]
+    pub r: u32,
+    #[arg(long, default_value = "1")]
+    pub p: u32,
+    /// Required for hex targets; generated hashes get a random salt unless
+    /// one is given
+    #[arg(long)]
+    pub salt: Option<String>,
+    #[arg(long, default_value = "32")]
+    pub key_length: usize,
+}
+
+fn digest<H: HashAlgorithm + 'static>(hasher: H, target_hash: &str, len: usize) -> Result<Box<dyn Verifier>> {
+    Ok(Box::new(DigestVerifier::new(hasher, target_hash, len)?))
+}
+
+pub static MD2: Algorithm = Algorithm {
+    name: "md2",
+    aliases: &[],
+    hashcat: None,
+    john: Some("md2"),
+    description: "MD2, hex digest",
+    example: "f03881a88c6e39135f0ecc60efd609b9",
+    output_len: 16,
+    salted: false,
+    iterated: false,
+    parse: |hash, _| digest(Md2Hash, hash, MD2.output_len),
+    generate: |_, _| Ok(Box::new(Md2Hash)),
+};
+
+pub static MD4: Algorithm = Algorithm {
+    name: "md4",
+    aliases: &[],
+    hashcat: Some(900),
+    john: Some("raw-md4"),
+    description: "MD4, hex digest",
+    example: "8a9d093f14f8701df17732b2bb182c74",
+    output_len: 16,
+    salted: false,
+    iterated: false,
+    parse: |hash, _| digest(Md4Hash, hash, MD4.output_len),
+    generate: |_, _| Ok(Box::new(Md4Hash)),
+};
+
+pub static MD5: Algorithm = Algorithm {
+    name: "md5",
+    aliases: &[],
+    hashcat: Some(0),
+    john: Some("raw-md5"),
+    description: "MD5, hex digest",
+    example: "5f4dcc3b5aa765d61d8327deb882cf99",
+    output_len: 16,
+    salted: false,
+    iterated: false,
+    parse: |hash, _| digest(Md5Hash, hash, MD5.output_len),
+    generate: |_, _| Ok(Box::new(Md5Hash)),
+};
+
+pub static MD6: Algorithm = Algorithm {
+    name: "md6",
+    aliases: &["md6-256"],
+    hashcat: None,
+    john: None,
+    description: "MD6-256, hex digest",
+    example: "fa3734736f13ba4a6d2d626a7d6c3de8437aca84c3e9c6e4f30d03faf08740d6",
+    output_len: 32,
+    salted: false,
+    iterated: false,
+    parse: |hash, _| digest(Md6Hash, hash, MD6.output_len),
+    generate: |_, _| Ok(Box::new(Md6Hash)),
+};
+
+pub static SHA1: Algorithm = Algorithm {
+    name: "sha1",
+    aliases: &[],
+    hashcat: Some(100),
+    john: Some("raw-sha1"),
+    description: "SHA-1, hex digest",
+    example: "5baa61e4c9b93f3f0682250b6cf8331b7ee68fd8",
+    output_len: 20,
+    salted: false,
+    iterated: false,
+    parse: |hash, _| digest(Sha1Hash, hash, SHA1.output_len),
+    generate: |_, _| Ok(Box::new(Sha1Hash)),
+};
+
+pub static SHA2: Algorithm = Algorithm {
+    name: "sha2",
+    aliases: &["sha256"],
+    hashcat: Some(1400),
+    john: Some("raw-sha256"),
+    description: "SHA-256, hex digest",
+    example: "5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8",
+    output_len: 32,
+    salted: false,
+    iterated: false,
+    parse: |hash, _| digest(Sha2Hash, hash, SHA2.output_len),
+    generate: |_, _| Ok(Box::new(Sha2Hash)),
+};
+
+pub static SHA3: Algorithm = Algorithm {
+    name: "sha3",
+    aliases: &["sha3-256"],
+    hashcat: Some(17400),
+    john: None,
+    description: "SHA3-256, hex digest",
+    example: "c0067d4af4e87f00dbac63b6156828237059172d1bbeac67427345d6a9fda484",
+    output_len: 32,
+    salted: false,
+    iterated: false,
+    parse: |hash, _| digest(Sha3Hash, hash, SHA3.output_len),
+    generate: |_, _| Ok(Box::new(Sha3Hash)),
+};
+
+pub static BCRYPT: Algorithm = Algorithm {
+    name: "bcrypt",
+    aliases: &[],
+    hashcat: Some(3200),
+    john: Some("bcrypt"),
+    description: "bcrypt modular crypt (`$2a$`, `$2b$`, `$2y$`)",
+    example: "$2b$05$.aLK2s3pPd8oPsg4EnzoeutsTFw/FYgIdy79FwRCc8lb2BSX8x492",
+    output_len: 23,
+    salted: true,
+    iterated: true,
+    parse: |hash, _| Ok(Box::new(BcryptVerifier::new(hash)?)),
+    generate: |cost, _| Ok(Box::new(BcryptHash::with_cost(cost.unwrap_or(DEFAULT_COST))?)),
+};
+
+pub static SCRYPT: Algorithm = Algorithm {
+    name: "scrypt",
+    aliases: &[],
+    hashcat: Some(8900),
+    john: Some("scrypt"),
+    description: "scrypt, `$scrypt$` or hashcat `SCRYPT:` encoded, or a hex key with the scrypt subcommand's parameters",
+    example: "$scrypt$ln=10,r=8,p=1$aGFzaF9mb3JnZQ==$K5PE5xsdQCfk4VLz+REVAzcrl/u2LQun8ZuRkvNXC7I=",
+    output_len: 32,
+    salted: true,
+    iterated: true,
+    parse: |hash, scrypt| {
+        // Encoded scrypt carries its own parameters
+        if hash.starts_with("$scrypt$") {
+            return Ok(Box::new(ScryptVerifier::new(hash)?));
+        }
+        if hash.starts_with("SCRYPT:") {
+            return Ok(Box::new(ScryptVerifier::from_hashcat(hash)?));
+        }
+        match scrypt {
+            Some(ScryptParams { n, r, p, salt: Some(salt), key_length }) => {
+                digest(ScryptHash::new(*n, *r, *p, salt.clone(), *key_length)?, hash, *key_length)
+            }
+            Some(ScryptParams { salt: None, .. }) => {
+                Err(Error::Param("Hex scrypt targets need the scrypt subcommand's --salt".into()))
+            }
+            // Without parameters there is nothing to derive a hex key with
+            None => Err(Error::Param("Scrypt parameters required for hex format verification".into())),
+        }
+    },
+    generate: |cost, scrypt| {
+        // The cost is log2 N, overriding the scrypt subcommand's --n
+        let n = |default: u32| match cost {
+            Some(cost) => 1u32
+                .checked_shl(cost)
+                .ok_or_else(|| Error::Param(format!("Invalid scrypt cost {}: log2 N must be below 32", cost))),
+            None => Ok(default),
+        };
+        let hasher = match scrypt {
+            Some(ScryptParams { n: default_n, r, p, salt, key_length }) => {
+                // A fresh salt per hash unless one was asked for
+                let salt = salt.as_ref().map_or_else(|| random_bytes(16), |salt| salt.clone().into_bytes());
+                ScryptHash::with_salt(n(*default_n)?, *r, *p, salt, *key_length)?
+            }
+            // Same defaults as the scrypt subcommand
+            None => ScryptHash::with_salt(n(16384)?, 8, 1, random_bytes(16), 32)?,
+        };
+        Ok(Box::new(hasher))
+    },
+};
+
+pub static PHPASS: Algorithm = Algorithm {
+    name: "phpass",
+    aliases: &["wordpress", "phpbb3"],
+    hashcat: Some(400),
+    john: Some("phpass"),
+    description: "WordPress / phpBB portable hashes (`$P$` / `$H$`)",
+    example: "$P$6HXQ6p1c1g9Jmu/CxeZIV.rfA0WtVk/",
+    output_len: 16,
+    salted: true,
+    iterated: true,
+    parse: |hash, _| Ok(Box::new(PhpassVerifier::new(hash)?)),
+    generate: |cost, _| Ok(Box::new(PhpassHash::with_count(cost.unwrap_or(DEFAULT_PHPASS_COUNT))?)),
+};
+
+/// Every supported algorithm, in the order `--help` lists them.
+pub static ALGORITHMS: &[HashMode] = &[
+    HashMode::MD2,
+    HashMode::MD4,
+    HashMode::MD5,
+    HashMode::MD6,
+    HashMode::SHA1,
+    HashMode::SHA2,
+    HashMode::SHA3,
+    HashMode::BCRYPT,
+    HashMode::SCRYPT,
+    HashMode::PHPASS,
+];
+
+/// A registered algorithm, as selected with `--mode`.
+#[derive(Clone, Copy)]
+pub struct HashMode(&'static Algorithm);
+
+impl HashMode {
+    pub const MD2: HashMode = HashMode(&MD2);
+    pub const MD4: HashMode = HashMode(&MD4);
+    pub const MD5: HashMode = HashMode(&MD5);
+    pub const MD6: HashMode = HashMode(&MD6);
+    pub const SHA1: HashMode = HashMode(&SHA1);
+    pub const SHA2: HashMode = HashMode(&SHA2);
+    pub const SHA3: HashMode = HashMode(&SHA3);
+    pub const BCRYPT: HashMode = HashMode(&BCRYPT);
+    pub const SCRYPT: HashMode = HashMode(&SCRYPT);
+    pub const PHPASS: HashMode = HashMode(&PHPASS);
+
+    /// Looks a mode up by name, alias, hashcat number or John format name,
+    /// ignoring case.
+    pub fn from_name(name: &str) -> Option<HashMode> {
+        ALGORITHMS.iter().copied().find(|mode| {
+            mode.to_possible_value()
+                .is_some_and(|value| value.matches(name, true))
+        })
+    }
+}
+
+impl Deref for HashMode {
+    type Target = Algorithm;
+
+    fn deref(&self) -> &Algorithm {
+        self.0
+    }
+}
+
+impl PartialEq for HashMode {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0)
+    }
+}
+
+impl fmt::Display for HashMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+impl fmt::Debug for HashMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+impl ValueEnum for HashMode {
+    fn value_variants<'a>() -> &'a [Self] {
+        ALGORITHMS
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        // hashcat numbers and John names are hidden aliases, `list-modes` shows them
+        let hashcat = self.hashcat.map(|number| number.to_string());
+        Some(
+            PossibleValue::new(self.name)
+                .aliases(self.aliases.iter().copied())
+                .aliases(hashcat)
+                .aliases(self.john)
+                .help(self.description),
+        )
+    }
+}
+
+/// Modes whose parser accepts `hash`, in registry order. Hex digests of
+/// the same length are ambiguous, so there may be several.
+pub fn identify(hash: &str) -> Vec<HashMode> {
+    ALGORITHMS
+        .iter()
+        .copied()
+        .filter(|mode| (mode.parse)(hash, None).is_ok())
+        .collect()
+}
+
+/// Parses `target_hash` into the verifier candidates are checked with.
+/// Hex scrypt targets need `scrypt` parameters; encoded ones carry their own.
+pub fn create_verifier(
+    mode: &HashMode,
+    target_hash: &str,
+    scrypt: Option<&ScryptParams>,
+) -> Result<Box<dyn Verifier>> {
+    (mode.parse)(target_hash, scrypt)
+}
+
+/// Builds a hasher with a fresh salt and the requested parameters.
+pub fn create_generator(
+    mode: &HashMode,
+    cost: Option<u32>,
+    scrypt: Option<&ScryptParams>,
+) -> Result<Box<dyn HashAlgorithm>> {
+    (mode.generate)(cost, scrypt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn examples_verify() {
+        for mode in ALGORITHMS {
+            let verifier = create_verifier(mode, mode.example, None)
+                .unwrap_or_else(|e| panic!("{} example doesn't parse: {}", mode, e));
+            assert!(verifier.verify(EXAMPLE_PLAINTEXT.as_bytes()), "{} example doesn't verify", mode);
+            assert!(!verifier.verify(b"wrong"), "{} example verifies a wrong password", mode);
+        }
+    }
+
+    #[test]
+    fn examples_are_identified() {
+        for mode in ALGORITHMS {
+            assert!(identify(mode.example).contains(mode), "{} example not identified", mode);
+        }
+    }
+
+    #[test]
+    fn hashcat_examples_verify() {
+        // From hashcat's example hashes, all of the plaintext "hashcat"
+        let examples = [
+            ("0", "8743b52063cd84097a65d1633f5c74f5"),
+            ("100", "b89eaac7e61417341b710b727768294d0e6a277b"),
+            ("1400", "127e6fbfe24a750e72930c220a8e138275656b8e5d8f48a98c3c92df2caba935"),
+            ("400", "$P$984478476IagS59wHZvyQMArzfx58u."),
+            ("3200", "$2a$05$LhayLxezLhK1LhWvKxCyLOj0j1u.Kj0jZ0pEmm134uzrQlFvQJLF6"),
+            ("8900", "SCRYPT:1024:1:1:MDIwMzMwNTQwNDQyNQ==:5FW+zWivLxgCWj7qLiQbeC8zaNQ+qdO0NUinvqyFcfo="),
+        ];
+        for (number, hash) in examples {
+            let mode = HashMode::from_name(number).unwrap_or_else(|| panic!("no mode for -m {}", number));
+            let verifier = create_verifier(&mode, hash, None).unwrap();
+            assert!(verifier.verify(b"hashcat"), "hashcat -m {} example doesn't verify", number);
+        }
+    }
+
+    #[test]
+    fn malformed_bcrypt_is_an_error() {
+        let example = HashMode::BCRYPT.example;
+        let malformed = [
+            format!("{}$", example),
+            "$2b$05$.aLK2s3pPd8oPsg4Enzoeuéaaaaaaaaaaaaaaaaaaaaaaaaaaaaa$".to_string(),
+            example[..example.len() - 1].to_string(),
+            format!("{}a", example),
+            "$2b$05$".to_string(),
+        ];
+        for hash in &malformed {
+            assert!(create_verifier(&HashMode::BCRYPT, hash, None).is_err(), "'{}' parses", hash);
+            assert!(!identify(hash).contains(&HashMode::BCRYPT), "'{}' identified as bcrypt", hash);
+        }
+    }
+
+    #[test]
+    fn bcrypt_prefixes() {
+        let example = HashMode::BCRYPT.example;
+        for prefix in ["$2a$", "$2y$"] {
+            let hash = format!("{}{}", prefix, &example[4..]);
+            let verifier = create_verifier(&HashMode::BCRYPT, &hash, None).unwrap();
+            assert!(verifier.verify(EXAMPLE_PLAINTEXT.as_bytes()), "{} doesn't verify", prefix);
+        }
+        let buggy = format!("$2x${}", &example[4..]);
+        assert!(matches!(create_verifier(&HashMode::BCRYPT, &buggy, None), Err(Error::Unsupported(_))));
+    }
+
+    #[test]
+    fn hashcat_and_john_names_resolve() {
+        for mode in ALGORITHMS {
+            if let Some(number) = mode.hashcat {
+                assert_eq!(HashMode::from_name(&number.to_string()), Some(*mode));
+            }
+            if let Some(john) = mode.john {
+                assert_eq!(HashMode::from_name(john), Some(*mode));
+                assert_eq!(HashMode::from_name(&john.to_uppercase()), Some(*mode));
+            }
+        }
+        assert_eq!(HashMode::from_name("raw-md5"), Some(HashMode::MD5));
+        assert_eq!(HashMode::from_name("1400"), Some(HashMode::SHA2));
+        assert_eq!(HashMode::from_name("99999"), None);
+    }
+
+    #[test]
+    fn names_are_unique() {
+        let mut seen = Vec::new();
+        for mode in ALGORITHMS {
+            let value = mode.to_possible_value().unwrap();
+            let mut names: Vec<String> = value.get_name_and_aliases().map(str::to_lowercase).collect();
+            // A John name may repeat the mode's own name
+            names.sort();
+            names.dedup();
+            for name in names {
+                assert!(!seen.contains(&name), "'{}' names more than one mode", name);
+                seen.push(name);
+            }
+        }
+    }
+
+    #[test]
+    fn generated_hashes_verify() {
+        let scrypt = ScryptParams { n: 1024, r: 8, p: 1, salt: None, key_length: 32 };
+        for mode in ALGORITHMS {
+            let cost = mode.iterated.then_some(if *mode == HashMode::BCRYPT { 4 } else { 7 });
+            let hash = create_generator(mode, cost, Some(&scrypt)).unwrap().encode(b"secret");
+            let verifier = create_verifier(mode, &hash, Some(&scrypt)).unwrap();
+            assert!(verifier.verify(b"secret"), "{} generated hash doesn't verify", mode);
+            if mode.salted {
+                let again = create_generator(mode, cost, Some(&scrypt)).unwrap().encode(b"secret");
+                assert_ne!(hash, again, "{} generated the same salt twice", mode);
+            }
+        }
+    }
+
+    #[test]
+    fn generated_scrypt_salt_is_encoded_once() {
+        use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+        let hash = create_generator(&HashMode::SCRYPT, None, None).unwrap().encode(b"secret");
+        let salt = hash.split('$').nth(3).unwrap();
+        assert_eq!(B64.decode(salt).unwrap().len(), 16, "salt of '{}'", hash);
+    }
+
+    #[test]
+    fn scrypt_cost_is_log2_n() {
+        let params = ScryptParams { n: 16384, r: 8, p: 1, salt: None, key_length: 32 };
+        let hash = create_generator(&HashMode::SCRYPT, Some(9), Some(&params)).unwrap().encode(b"secret");
+        assert!(hash.starts_with("$scrypt$ln=9,"), "{}", hash);
+        assert!(create_generator(&HashMode::SCRYPT, Some(32), None).is_err());
+        assert!(create_generator(&HashMode::SCRYPT, Some(0), None).is_err());
+    }
+
+    #[test]
+    fn hex_scrypt_needs_a_salt() {
+        let params = ScryptParams { n: 1024, r: 8, p: 1, salt: None, key_length: 32 };
+        let key = "00".repeat(32);
+        assert!(create_verifier(&HashMode::SCRYPT, &key, Some(&params)).is_err());
+        let params = ScryptParams { salt: Some("salt".into()), ..params };
+        assert!(create_verifier(&HashMode::SCRYPT, &key, Some(&params)).is_ok());
+    }
+}
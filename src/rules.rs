@@ -0,0 +1,180 @@
+//! Word mangling rules in the hashcat/John rule syntax, the common subset
+//! both understand. Case changes are ASCII only.
+
+use std::{fmt, str::FromStr};
+use crate::error::{Error, Result};
+
+/// One rule function.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    /// `:`
+    Nothing,
+    /// `l`
+    Lower,
+    /// `u`
+    Upper,
+    /// `c`, first letter upper, the rest lower
+    Capitalize,
+    /// `C`, first letter lower, the rest upper
+    InvertCapitalize,
+    /// `t`
+    ToggleCase,
+    /// `TN`
+    ToggleAt(usize),
+    /// `r`
+    Reverse,
+    /// `d`
+    Duplicate,
+    /// `f`, the word followed by its reverse
+    Reflect,
+    /// `{`
+    RotateLeft,
+    /// `}`
+    RotateRight,
+    /// `$X`
+    Append(u8),
+    /// `^X`
+    Prepend(u8),
+    /// `[`
+    DeleteFirst,
+    /// `]`
+    DeleteLast,
+    /// `DN`
+    DeleteAt(usize),
+    /// `'N`, keep the first N bytes
+    Truncate(usize),
+    /// `sXY`, every X becomes Y
+    Replace(u8, u8),
+    /// `@X`, every X is removed
+    Purge(u8),
+}
+
+/// A sequence of rule functions applied to a word in order, e.g. `c$1$2`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    ops: Vec<Op>,
+    text: String,
+}
+
+impl Rule {
+    pub fn apply(&self, word: &[u8]) -> Vec<u8> {
+        let mut word = word.to_vec();
+        for op in &self.ops {
+            match *op {
+                Op::Nothing => {}
+                Op::Lower => word.make_ascii_lowercase(),
+                Op::Upper => word.make_ascii_uppercase(),
+                Op::Capitalize => {
+                    word.make_ascii_lowercase();
+                    if let Some(first) = word.first_mut() {
+                        first.make_ascii_uppercase();
+                    }
+                }
+                Op::InvertCapitalize => {
+                    word.make_ascii_uppercase();
+                    if let Some(first) = word.first_mut() {
+                        first.make_ascii_lowercase();
+                    }
+                }
+                Op::ToggleCase => word.iter_mut().for_each(toggle),
+                Op::ToggleAt(n) => {
+                    if let Some(b) = word.get_mut(n) {
+                        toggle(b);
+                    }
+                }
+                Op::Reverse => word.reverse(),
+                Op::Duplicate => word.extend_from_within(..),
+                Op::Reflect => {
+                    let reversed: Vec<u8> = word.iter().rev().copied().collect();
+                    word.extend(reversed);
+                }
+                Op::RotateLeft if !word.is_empty() => word.rotate_left(1),
+                Op::RotateRight if !word.is_empty() => word.rotate_right(1),
+                Op::RotateLeft | Op::RotateRight => {}
+                Op::Append(b) => word.push(b),
+                Op::Prepend(b) => word.insert(0, b),
+                Op::DeleteFirst if !word.is_empty() => {
+                    word.remove(0);
+                }
+                Op::DeleteLast => {
+                    word.pop();
+                }
+                Op::DeleteAt(n) if n < word.len() => {
+                    word.remove(n);
+                }
+                Op::DeleteFirst | Op::DeleteAt(_) => {}
+                Op::Truncate(n) => word.truncate(n),
+                Op::Replace(from, to) => word.iter_mut().filter(|b| **b == from).for_each(|b| *b = to),
+                Op::Purge(b) => word.retain(|&c| c != b),
+            }
+        }
+        word
+    }
+}
+
+/// `:`, the word as it is.
+impl Default for Rule {
+    fn default() -> Self {
+        Self { ops: Vec::new(), text: ":".into() }
+    }
+}
+
+fn toggle(b: &mut u8) {
+    if b.is_ascii_lowercase() {
+        b.make_ascii_uppercase();
+    } else {
+        b.make_ascii_lowercase();
+    }
+}
+
+impl FromStr for Rule {
+    type Err = Error;
+
+    /// Parses a rule; spaces between functions are ignored, as in rule files.
+    fn from_str(text: &str) -> Result<Self> {
+        let invalid = |why: String| Error::Parse(format!("Invalid rule '{}': {}", text, why));
+        let position = |b: u8| match b {
+            b'0'..=b'9' => Ok((b - b'0') as usize),
+            b'A'..=b'Z' => Ok((b - b'A') as usize + 10),
+            _ => Err(invalid(format!("'{}' isn't a position (0-9, A-Z)", b as char))),
+        };
+
+        let mut ops = Vec::new();
+        let mut bytes = text.bytes();
+        while let Some(name) = bytes.next() {
+            let mut arg = || bytes.next().ok_or_else(|| invalid(format!("'{}' needs an argument", name as char)));
+            let op = match name {
+                b' ' => continue,
+                b':' => Op::Nothing,
+                b'l' => Op::Lower,
+                b'u' => Op::Upper,
+                b'c' => Op::Capitalize,
+                b'C' => Op::InvertCapitalize,
+                b't' => Op::ToggleCase,
+                b'r' => Op::Reverse,
+                b'd' => Op::Duplicate,
+                b'f' => Op::Reflect,
+                b'{' => Op::RotateLeft,
+                b'}' => Op::RotateRight,
+                b'[' => Op::DeleteFirst,
+                b']' => Op::DeleteLast,
+                b'T' => Op::ToggleAt(position(arg()?)?),
+                b'D' => Op::DeleteAt(position(arg()?)?),
+                b'\'' => Op::Truncate(position(arg()?)?),
+                b'$' => Op::Append(arg()?),
+                b'^' => Op::Prepend(arg()?),
+                b'@' => Op::Purge(arg()?),
+                b's' => Op::Replace(arg()?, arg()?),
+                other => return Err(invalid(format!("unknown function '{}'", other as char))),
+            };
+            ops.push(op);
+        }
+        Ok(Self { ops, text: text.to_string() })
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
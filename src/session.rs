@@ -0,0 +1,120 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+use serde::{Deserialize, Serialize};
+use hash_forge::crack::Position;
+use hash_forge::error::{Error, Result};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Installs the Ctrl-C handler. The first Ctrl-C asks the run to stop at the
+/// next candidate and write a final checkpoint, a second one exits at once.
+pub fn install_interrupt_handler() -> Result<()> {
+    ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            std::process::exit(130);
+        }
+    })
+    .map_err(|e| Error::Unsupported(format!("Error installing Ctrl-C handler: {}", e)))
+}
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// A target cracked before the checkpoint was written.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Cracked {
+    pub hash: String,
+    pub plaintext_hex: String,
+}
+
+/// Everything needed to continue a run: its command line, where it stopped
+/// and what it already cracked.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Session {
+    pub args: Vec<String>,
+    pub position: Position,
+    pub cracked: Vec<Cracked>,
+}
+
+/// `NAME.session` in the working directory, or the path itself if it
+/// already names a session file.
+pub fn session_path(name: &str) -> PathBuf {
+    if name.ends_with(".session") {
+        PathBuf::from(name)
+    } else {
+        PathBuf::from(format!("{}.session", name))
+    }
+}
+
+impl Session {
+    pub fn load(name: &str) -> Result<Self> {
+        let path = session_path(name);
+        let data = fs::read_to_string(&path)
+            .map_err(Error::io(format!("Error reading session {}", path.display())))?;
+        serde_json::from_str(&data)
+            .map_err(|e| Error::Parse(format!("Invalid session file {}: {}", path.display(), e)))
+    }
+}
+
+/// Writes the session file periodically and on interruption.
+pub struct Checkpoint {
+    path: PathBuf,
+    args: Vec<String>,
+    due: Arc<AtomicBool>,
+}
+
+impl Checkpoint {
+    /// Starts a timer that marks a checkpoint as due every `interval`.
+    pub fn new(name: &str, args: Vec<String>, interval: Duration) -> Self {
+        let due = Arc::new(AtomicBool::new(false));
+        let timer = Arc::clone(&due);
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            timer.store(true, Ordering::Relaxed);
+        });
+
+        Self {
+            path: session_path(name),
+            args,
+            due,
+        }
+    }
+
+    /// True once per interval; cheap enough to call for every candidate.
+    pub fn is_due(&self) -> bool {
+        self.due.load(Ordering::Relaxed) && self.due.swap(false, Ordering::Relaxed)
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Atomically replaces the session file with the current state.
+    pub fn save(&self, position: &Position, cracked: &[Cracked]) -> Result<()> {
+        let session = Session {
+            args: self.args.clone(),
+            position: position.clone(),
+            cracked: cracked.to_vec(),
+        };
+        let data = serde_json::to_string_pretty(&session)
+            .map_err(|e| Error::Io { context: "Error encoding session".into(), source: e.into() })?;
+        let tmp = self.path.with_extension("session.tmp");
+        fs::write(&tmp, data)
+            .and_then(|_| fs::rename(&tmp, &self.path))
+            .map_err(Error::io(format!("Error writing session {}", self.path.display())))
+    }
+
+    /// Deletes the session file once the run has finished.
+    pub fn remove(&self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
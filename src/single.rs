@@ -0,0 +1,140 @@
+//! Single crack mode: candidates made from each target's own account
+//! details, the way John the Ripper's single mode does it.
+
+use std::{collections::HashSet, sync::LazyLock};
+use crate::rules::Rule;
+use crate::targets::Target;
+
+/// Built-in mangling rules, each applied to every word of a target in turn.
+const RULES: &[&str] = &[
+    ":", "l", "u", "c", "C", "t", "r", "lr", "d", "f", "cd",
+    "$1", "$2", "$!", "$1$2", "$1$2$3", "$0$1", "$1$1", "$@", "$.",
+    "c$1", "c$2", "c$!", "c$1$2", "c$1$2$3", "c$@", "l$1", "u$1", "^1", "c^1",
+    "$2$0$2$4", "$2$0$2$5", "$2$0$2$6", "c$2$0$2$4", "c$2$0$2$5", "c$2$0$2$6",
+    "$2$4", "$2$5", "$2$6", "c$2$4", "c$2$5", "c$2$6",
+    "sa@", "so0", "se3", "si1", "ss$", "l sa@ so0 se3", "c sa@ so0 se3 si1",
+    "[", "]", "c]", "{", "}",
+];
+
+static PARSED: LazyLock<Vec<Rule>> =
+    LazyLock::new(|| RULES.iter().map(|rule| rule.parse().expect("built-in rule")).collect());
+
+/// Words describing the account: its login name, the parts of its full
+/// name and a few of their combinations, and the last component of its
+/// home directory.
+pub fn words(target: &Target) -> Vec<String> {
+    let mut words = Vec::new();
+    if let Some(user) = &target.user {
+        // `DOMAIN\user` in NTDS dumps
+        let login = user.rsplit('\\').next().unwrap_or(user);
+        words.push(login.to_string());
+        words.push(login.trim_end_matches(|c: char| c.is_ascii_digit()).to_string());
+    }
+    if let Some(gecos) = &target.gecos {
+        // Full name, office, phone, ...; only the full name says much
+        let name = gecos.split(',').next().unwrap_or_default();
+        let parts: Vec<&str> = name
+            .split(|c: char| c.is_whitespace() || matches!(c, '.' | '-' | '_'))
+            .filter(|part| !part.is_empty())
+            .collect();
+        words.extend(parts.iter().map(|part| part.to_string()));
+        if let [first, .., last] = parts[..] {
+            let initial = |part: &str| part.chars().next().map(String::from).unwrap_or_default();
+            words.push(format!("{}{}", first, last));
+            words.push(format!("{}{}", last, first));
+            words.push(format!("{}{}", initial(first), last));
+            words.push(format!("{}{}", first, initial(last)));
+            words.push(parts.iter().map(|part| initial(part)).collect());
+        }
+    }
+    if let Some(home) = &target.home {
+        words.extend(home.rsplit('/').find(|part| !part.is_empty()).map(String::from));
+    }
+
+    let mut seen = HashSet::new();
+    words.retain(|word| !word.is_empty() && seen.insert(word.to_lowercase()));
+    words
+}
+
+/// Candidates for `target`, without repeats: the first rule over every
+/// word, then the next rule, and so on.
+pub fn candidates(target: &Target) -> Vec<Vec<u8>> {
+    let words = words(target);
+    let mut seen = HashSet::new();
+    let mut candidates = Vec::new();
+    for rule in PARSED.iter() {
+        for word in &words {
+            let candidate = rule.apply(word.as_bytes());
+            if !candidate.is_empty() && seen.insert(candidate.clone()) {
+                candidates.push(candidate);
+            }
+        }
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crack::{Cracker, Outcome, Position};
+    use crate::registry::{create_generator, create_verifier, HashMode};
+
+    fn account(user: &str, gecos: Option<&str>, home: Option<&str>) -> Target {
+        Target {
+            user: Some(user.into()),
+            gecos: gecos.map(String::from),
+            home: home.map(String::from),
+            ..Target::bare("")
+        }
+    }
+
+    #[test]
+    fn words_come_from_login_full_name_and_home() {
+        let target = account("jsmith42", Some("John Smith,Room 12,555-1234"), Some("/home/jsmith/"));
+        // JSmith and the home directory repeat the login but for case
+        assert_eq!(
+            words(&target),
+            ["jsmith42", "jsmith", "John", "Smith", "JohnSmith", "SmithJohn", "JohnS", "JS"]
+        );
+        let target = account("CORP\\alice", None, Some("/srv/www"));
+        assert_eq!(words(&target), ["alice", "www"]);
+        assert!(words(&Target::bare("5f4dcc3b5aa765d61d8327deb882cf99")).is_empty());
+    }
+
+    #[test]
+    fn candidates_are_mangled_words_without_repeats() {
+        let target = account("bob", Some("Bob Marley"), None);
+        let candidates = candidates(&target);
+        for expected in ["bob", "Bob", "BOB", "bob1", "Bob2024", "Marley!", "BobMarley", "b0b"] {
+            assert!(candidates.contains(&expected.as_bytes().to_vec()), "no '{}'", expected);
+        }
+        let mut unique = candidates.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), candidates.len());
+        assert!(candidates.iter().all(|candidate| !candidate.is_empty()));
+        // The word as it is comes first
+        assert_eq!(candidates[0], b"bob");
+    }
+
+    #[test]
+    fn candidates_are_tried_against_their_own_target_only() {
+        let md5 = |word: &[u8]| create_generator(&HashMode::MD5, None, None).unwrap().encode(word);
+        // alice's password comes from bob's name and the other way round
+        let targets = [
+            Target { hash: md5(b"bob1"), ..account("alice", None, None) },
+            Target { hash: md5(b"Alice1"), ..account("bob", None, None) },
+            Target { hash: md5(b"carol1"), ..account("carol", None, None) },
+        ];
+        let verifiers: Vec<_> = targets
+            .iter()
+            .map(|target| create_verifier(&HashMode::MD5, &target.hash, None).unwrap())
+            .collect();
+        let candidates: Vec<_> = targets.iter().map(|target| Some(candidates(target))).collect();
+
+        let cracker = Cracker::new(verifiers.iter().map(|verifier| verifier.as_ref()).collect());
+        let report = cracker.run_single(&candidates, &Position::default(), &mut ());
+        assert_eq!(report.outcome, Outcome::Exhausted);
+        assert_eq!(report.cracked, [(2, b"carol1".to_vec())]);
+    }
+}
@@ -0,0 +1,251 @@
+use std::{
+    io::{self, IsTerminal, Write},
+    time::{Duration, Instant},
+};
+use serde::Serialize;
+use hash_forge::logging::timestamp;
+
+/// How often the status line is redrawn.
+const INTERVAL: Duration = Duration::from_secs(1);
+
+/// One status report, also emitted as a record by the JSON output formats.
+#[derive(Serialize, Debug, Clone)]
+pub struct Snapshot {
+    pub elapsed_secs: f64,
+    pub tried: u64,
+    /// Share of the wordlist bytes read, when their total size is known
+    pub progress_percent: Option<f64>,
+    pub eta_secs: Option<f64>,
+    /// Hashes per second since the previous report
+    pub speed: f64,
+    pub cracked: usize,
+    pub targets: usize,
+    pub timestamp: String,
+}
+
+/// Periodic status line on stderr: candidates tried, wordlist progress by
+/// bytes, current speed, ETA, cracked targets and elapsed time.
+pub struct Status {
+    start: Instant,
+    last_report: Instant,
+    last_tried: u64,
+    tried: u64,
+    pacer: Pacer,
+    bytes_done: u64,
+    /// Bytes covered by a restored session, left out of the rate for the ETA
+    bytes_resumed: u64,
+    source_pos: u64,
+    total_bytes: Option<u64>,
+    cracked: usize,
+    targets: usize,
+    /// Redraw in place with `\r`, only when stderr is a terminal
+    inline: bool,
+    drawn: bool,
+    /// Show the line on stderr
+    draw: bool,
+    /// Keep each report for [`Status::take_snapshot`]
+    records: bool,
+    latest: Option<Snapshot>,
+}
+
+impl Status {
+    /// `draw` shows the line on stderr, `records` keeps every report for
+    /// the JSON output formats; with neither, nothing is timed at all.
+    pub fn new(total_bytes: Option<u64>, targets: usize, draw: bool, records: bool) -> Self {
+        let now = Instant::now();
+        Self {
+            start: now,
+            last_report: now,
+            last_tried: 0,
+            tried: 0,
+            pacer: Pacer::new(INTERVAL),
+            bytes_done: 0,
+            bytes_resumed: 0,
+            source_pos: 0,
+            total_bytes,
+            cracked: 0,
+            targets,
+            inline: io::stderr().is_terminal(),
+            drawn: false,
+            draw,
+            records,
+            latest: None,
+        }
+    }
+
+    /// Carries over progress from a restored session.
+    pub fn resume(&mut self, tried: u64, bytes_done: u64) {
+        self.tried = tried;
+        self.last_tried = tried;
+        self.pacer.resume(tried);
+        self.bytes_done = bytes_done;
+        self.bytes_resumed = bytes_done;
+    }
+
+    /// Byte offset a restored session picked up from inside its first source.
+    pub fn resume_source(&mut self, source_pos: u64) {
+        self.source_pos = source_pos;
+        self.bytes_resumed = self.bytes_done + source_pos;
+    }
+
+    /// Records one tried candidate; `source_pos` is the byte offset reached
+    /// in the current source.
+    pub fn candidate(&mut self, source_pos: u64) {
+        self.tried += 1;
+        self.source_pos = source_pos;
+        if self.enabled()
+            && let Some(now) = self.pacer.tick(self.tried)
+        {
+            self.report(now);
+        }
+    }
+
+    /// Marks the current source as fully read.
+    pub fn source_done(&mut self) {
+        self.bytes_done += self.source_pos;
+        self.source_pos = 0;
+    }
+
+    pub fn cracked(&mut self) {
+        self.cracked += 1;
+    }
+
+    /// Erases an in-place status line so other output starts on a clean line.
+    pub fn clear(&mut self) {
+        if self.drawn && self.inline {
+            eprint!("\r\x1b[2K");
+            let _ = io::stderr().flush();
+        }
+        self.drawn = false;
+    }
+
+    /// Prints a last status line, if any were shown at all.
+    pub fn finish(&mut self) {
+        if self.enabled() && self.start.elapsed() >= INTERVAL {
+            self.report(Instant::now());
+        }
+        if self.drawn && self.inline {
+            eprintln!();
+        }
+        self.drawn = false;
+    }
+
+    pub fn tried(&self) -> u64 {
+        self.tried
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// The report made since the last call, if any.
+    pub fn take_snapshot(&mut self) -> Option<Snapshot> {
+        self.latest.take()
+    }
+
+    fn report(&mut self, now: Instant) {
+        let window = now.duration_since(self.last_report).as_secs_f64().max(1e-3);
+        let elapsed = now.duration_since(self.start);
+        let done = self.bytes_done + self.source_pos;
+        let mut snapshot = Snapshot {
+            elapsed_secs: elapsed.as_secs_f64(),
+            tried: self.tried,
+            progress_percent: None,
+            eta_secs: None,
+            speed: (self.tried - self.last_tried) as f64 / window,
+            cracked: self.cracked,
+            targets: self.targets,
+            timestamp: timestamp(),
+        };
+        if let Some(total) = self.total_bytes.filter(|&total| total > 0) {
+            snapshot.progress_percent = Some(done as f64 * 100.0 / total as f64);
+            let byte_rate = done.saturating_sub(self.bytes_resumed) as f64 / elapsed.as_secs_f64().max(1e-3);
+            if byte_rate > 0.0 {
+                snapshot.eta_secs = Some(total.saturating_sub(done) as f64 / byte_rate);
+            }
+        }
+
+        if self.draw {
+            let mut line = format!("[{}] {} tried", format_duration(elapsed), self.tried);
+            if let Some(percent) = snapshot.progress_percent {
+                line += &format!(" | {:.1}%", percent);
+            }
+            if let Some(eta) = snapshot.eta_secs {
+                line += &format!(" | ETA {}", format_duration(Duration::from_secs_f64(eta)));
+            }
+            line += &format!(" | {} | cracked {}/{}", format_speed(snapshot.speed), self.cracked, self.targets);
+
+            if self.inline {
+                eprint!("\r\x1b[2K{}", line);
+                let _ = io::stderr().flush();
+            } else {
+                eprintln!("{}", line);
+            }
+            self.drawn = true;
+        }
+        self.last_report = now;
+        self.last_tried = self.tried;
+        if self.records {
+            self.latest = Some(snapshot);
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        self.draw || self.records
+    }
+}
+
+/// Tells a loop over candidates when an interval has passed. The clock is
+/// only read every so many candidates, a stride adapted to the speed, so
+/// fast hashes don't pay for it on every one and slow ones aren't late.
+pub struct Pacer {
+    interval: Duration,
+    last: Instant,
+    last_count: u64,
+    next_check: u64,
+}
+
+impl Pacer {
+    pub fn new(interval: Duration) -> Self {
+        Self { interval, last: Instant::now(), last_count: 0, next_check: 1 }
+    }
+
+    /// Counts on from `count` candidates, e.g. those of a restored session.
+    pub fn resume(&mut self, count: u64) {
+        self.last_count = count;
+        self.next_check = count + 1;
+    }
+
+    /// Called with the candidates done so far; returns the time whenever
+    /// the interval has passed since it last did.
+    pub fn tick(&mut self, count: u64) -> Option<Instant> {
+        if count < self.next_check {
+            return None;
+        }
+        let now = Instant::now();
+        let since = now.duration_since(self.last);
+        // Aim for roughly 20 clock checks per interval at the current speed
+        let per_sec = (count - self.last_count) as f64 / since.as_secs_f64().max(1e-3);
+        self.next_check = count + ((per_sec / 20.0) as u64).clamp(1, 1 << 16);
+        if since < self.interval {
+            return None;
+        }
+        self.last = now;
+        self.last_count = count;
+        Some(now)
+    }
+}
+
+pub fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+pub fn format_speed(per_sec: f64) -> String {
+    match per_sec {
+        s if s >= 1e9 => format!("{:.2} GH/s", s / 1e9),
+        s if s >= 1e6 => format!("{:.2} MH/s", s / 1e6),
+        s if s >= 1e3 => format!("{:.2} kH/s", s / 1e3),
+        s => format!("{:.1} H/s", s),
+    }
+}
@@ -0,0 +1,349 @@
+//! Target files: hash dumps in the layouts password files and dumping tools
+//! write them in, one account per line.
+
+use std::{
+    fmt,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+use crate::error::{Error, Result};
+
+/// Layout a target line was recognized as.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// Just the hash
+    Bare,
+    /// `user:hash`, as in Apache htpasswd files
+    Htpasswd,
+    /// `user:hash:salt`
+    UserHashSalt,
+    /// `/etc/shadow`: `user:hash:lastchg:min:max:warn:inactive:expire:`
+    Shadow,
+    /// `/etc/passwd` with the hashes merged in, as `unshadow` writes it
+    Passwd,
+    /// `user:rid:lm:nt:::`
+    Pwdump,
+    /// secretsdump output for an NTDS.dit: pwdump lines with `domain\user`
+    Ntds,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Format::Bare => "bare",
+            Format::Htpasswd => "htpasswd",
+            Format::UserHashSalt => "user:hash:salt",
+            Format::Shadow => "shadow",
+            Format::Passwd => "passwd",
+            Format::Pwdump => "pwdump",
+            Format::Ntds => "ntds",
+        };
+        f.write_str(name)
+    }
+}
+
+/// One hash to crack and the account it belongs to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Target {
+    pub hash: String,
+    pub user: Option<String>,
+    /// Salt given in a field of its own, on `user:hash:salt` lines
+    pub salt: Option<String>,
+    /// GECOS field (full name, office, phone) of passwd lines
+    pub gecos: Option<String>,
+    /// Home directory of passwd lines
+    pub home: Option<String>,
+    /// Line of the target file, counting from 1; 0 for a hash given directly
+    pub line: u64,
+    pub format: Format,
+}
+
+impl Target {
+    /// A hash given on its own, e.g. on the command line.
+    pub fn bare(hash: &str) -> Self {
+        Self {
+            hash: hash.to_string(),
+            user: None,
+            salt: None,
+            gecos: None,
+            home: None,
+            line: 0,
+            format: Format::Bare,
+        }
+    }
+}
+
+/// What one line of a target file holds.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Entry {
+    Target(Target),
+    /// An account without a crackable hash: locked, disabled or passwordless
+    Skipped(String),
+    /// A line in none of the known layouts
+    Malformed(String),
+}
+
+/// A line that didn't give a target, and why.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rejected {
+    pub line: u64,
+    pub reason: String,
+}
+
+/// Everything read from a target file.
+#[derive(Debug, Default)]
+pub struct TargetList {
+    pub targets: Vec<Target>,
+    pub skipped: Vec<Rejected>,
+    pub malformed: Vec<Rejected>,
+}
+
+impl TargetList {
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = File::open(path).map_err(Error::io(format!("Error opening target file {}", path.display())))?;
+        Self::parse(BufReader::new(file))
+            .map_err(|e| match e {
+                Error::Io { source, .. } => {
+                    Error::Io { context: format!("Error reading target file {}", path.display()), source }
+                }
+                e => e,
+            })
+    }
+
+    /// Reads every line of `reader`; lines that aren't UTF-8 count as malformed.
+    pub fn parse(reader: impl BufRead) -> Result<Self> {
+        let mut list = Self::default();
+        for (n, line) in reader.split(b'\n').enumerate() {
+            let line = line.map_err(Error::io("Error reading targets"))?;
+            let line_no = n as u64 + 1;
+            let entry = match std::str::from_utf8(&line) {
+                Ok(text) => parse_line(text, line_no),
+                Err(_) => Some(Entry::Malformed("not valid UTF-8".into())),
+            };
+            match entry {
+                Some(Entry::Target(target)) => list.targets.push(target),
+                Some(Entry::Skipped(reason)) => list.skipped.push(Rejected { line: line_no, reason }),
+                Some(Entry::Malformed(reason)) => list.malformed.push(Rejected { line: line_no, reason }),
+                None => {}
+            }
+        }
+        Ok(list)
+    }
+}
+
+/// Key types secretsdump lists after the NT hashes; not hashes of a password.
+const KERBEROS_KEYS: &[&str] = &[
+    "aes256-cts-hmac-sha1-96",
+    "aes128-cts-hmac-sha1-96",
+    "des-cbc-md5",
+    "des-cbc-crc",
+    "rc4_hmac",
+    "CLEARTEXT",
+];
+
+/// Recognizes the layout of one line by its fields. Blank lines and `#`
+/// comments give `None`.
+pub fn parse_line(text: &str, line: u64) -> Option<Entry> {
+    let text = text.trim();
+    if text.is_empty() || text.starts_with('#') {
+        return None;
+    }
+    let target = |hash: &str, user: &str, format| Target {
+        hash: hash.to_string(),
+        user: Some(user.to_string()),
+        salt: None,
+        gecos: None,
+        home: None,
+        line,
+        format,
+    };
+
+    // hashcat's scrypt format has colons of its own
+    if !text.contains(':') || text.starts_with("SCRYPT:") {
+        return Some(Entry::Target(Target { line, ..Target::bare(text) }));
+    }
+    if let Some((user, hash)) = text.split_once(':')
+        && hash.starts_with("SCRYPT:")
+    {
+        return Some(Entry::Target(target(hash, user, Format::Htpasswd)));
+    }
+
+    let fields: Vec<&str> = text.split(':').collect();
+    let user = fields[0];
+    if user.is_empty() {
+        return Some(Entry::Malformed("empty user name".into()));
+    }
+    let numeric = |field: &str| !field.is_empty() && field.bytes().all(|b| b.is_ascii_digit());
+    let entry = match fields.len() {
+        2 => with_hash(fields[1], || target(fields[1], user, Format::Htpasswd)),
+        3 if KERBEROS_KEYS.contains(&fields[1]) => Entry::Skipped(format!("{} key, not a password hash", fields[1])),
+        3 => with_hash(fields[1], || Target {
+            salt: Some(fields[2].to_string()).filter(|salt| !salt.is_empty()),
+            ..target(fields[1], user, Format::UserHashSalt)
+        }),
+        7 if numeric(fields[1]) && fields[2].len() == 32 && fields[3].len() == 32 => {
+            let nt = fields[3];
+            let format = if user.contains('\\') { Format::Ntds } else { Format::Pwdump };
+            if nt.starts_with("NO PASSWORD") {
+                Entry::Skipped("no NT hash".into())
+            } else if !nt.bytes().all(|b| b.is_ascii_hexdigit()) {
+                Entry::Malformed("NT hash isn't hex".into())
+            } else {
+                Entry::Target(target(nt, user, format))
+            }
+        }
+        7 if numeric(fields[2]) && numeric(fields[3]) => match fields[1] {
+            "x" => Entry::Skipped("hash is in the shadow file".into()),
+            hash => account(hash).unwrap_or_else(|| {
+                Entry::Target(Target {
+                    gecos: Some(fields[4].to_string()).filter(|gecos| !gecos.is_empty()),
+                    home: Some(fields[5].to_string()).filter(|home| !home.is_empty()),
+                    ..target(hash, user, Format::Passwd)
+                })
+            }),
+        },
+        7 => Entry::Malformed("7 fields, but neither pwdump nor passwd".into()),
+        9 if fields[2..8].iter().all(|field| field.is_empty() || numeric(field)) => {
+            account(fields[1]).unwrap_or_else(|| Entry::Target(target(fields[1], user, Format::Shadow)))
+        }
+        9 => Entry::Malformed("9 fields, but not a shadow line".into()),
+        n => Entry::Malformed(format!("unrecognized layout with {} fields", n)),
+    };
+    Some(entry)
+}
+
+/// Turns `hash` into a target, unless the field is empty.
+fn with_hash(hash: &str, target: impl FnOnce() -> Target) -> Entry {
+    if hash.is_empty() {
+        Entry::Malformed("empty hash".into())
+    } else {
+        Entry::Target(target())
+    }
+}
+
+/// Password fields of shadow and passwd lines that aren't hashes.
+fn account(hash: &str) -> Option<Entry> {
+    if hash.is_empty() {
+        Some(Entry::Skipped("no password".into()))
+    } else if hash.starts_with('!') || hash.starts_with('*') {
+        Some(Entry::Skipped("locked or disabled account".into()))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MD5: &str = "5f4dcc3b5aa765d61d8327deb882cf99";
+    const NT: &str = "8846f7eaee8fb117ad06bdd830b7586c";
+    const LM: &str = "aad3b435b51404eeaad3b435b51404ee";
+
+    fn target(text: &str) -> Target {
+        match parse_line(text, 7) {
+            Some(Entry::Target(target)) => target,
+            other => panic!("'{}' gave {:?}", text, other),
+        }
+    }
+
+    fn skipped(text: &str) -> bool {
+        matches!(parse_line(text, 1), Some(Entry::Skipped(_)))
+    }
+
+    fn malformed(text: &str) -> bool {
+        matches!(parse_line(text, 1), Some(Entry::Malformed(_)))
+    }
+
+    #[test]
+    fn formats_are_recognized() {
+        let bare = target(MD5);
+        assert_eq!((bare.format, bare.hash.as_str(), bare.user, bare.line), (Format::Bare, MD5, None, 7));
+
+        let htpasswd = target("web:$apr1$salt$hash");
+        assert_eq!((htpasswd.format, htpasswd.hash.as_str()), (Format::Htpasswd, "$apr1$salt$hash"));
+        assert_eq!(htpasswd.user.as_deref(), Some("web"));
+
+        let salted = target(&format!("bob:{}:pepper", MD5));
+        assert_eq!((salted.format, salted.hash.as_str()), (Format::UserHashSalt, MD5));
+        assert_eq!(salted.salt.as_deref(), Some("pepper"));
+        assert_eq!(target(&format!("bob:{}:", MD5)).salt, None);
+
+        let shadow = target("root:$6$salt$hash:19000:0:99999:7:::");
+        assert_eq!((shadow.format, shadow.hash.as_str()), (Format::Shadow, "$6$salt$hash"));
+        assert_eq!(shadow.user.as_deref(), Some("root"));
+
+        let passwd = target(&format!("dave:{}:1001:1001:Dave Smith,,,:/home/dave:/bin/bash", MD5));
+        assert_eq!((passwd.format, passwd.hash.as_str()), (Format::Passwd, MD5));
+        assert_eq!(passwd.gecos.as_deref(), Some("Dave Smith,,,"));
+        assert_eq!(passwd.home.as_deref(), Some("/home/dave"));
+
+        let pwdump = target(&format!("Administrator:500:{}:{}:::", LM, NT));
+        assert_eq!((pwdump.format, pwdump.hash.as_str()), (Format::Pwdump, NT));
+        assert_eq!(pwdump.user.as_deref(), Some("Administrator"));
+
+        let ntds = target(&format!("CORP\\jdoe:1104:{}:{}::: (status=Enabled)", LM, NT));
+        assert_eq!((ntds.format, ntds.hash.as_str()), (Format::Ntds, NT));
+        assert_eq!(ntds.user.as_deref(), Some("CORP\\jdoe"));
+    }
+
+    #[test]
+    fn hashcat_scrypt_keeps_its_colons() {
+        let hash = "SCRYPT:1024:1:1:MDIwMzMwNTQwNDQyNQ==:5FW+zWivLxgCWj7qLiQbeC8zaNQ+qdO0NUinvqyFcfo=";
+        let bare = target(hash);
+        assert_eq!((bare.format, bare.hash.as_str()), (Format::Bare, hash));
+
+        let owned = target(&format!("alice:{}", hash));
+        assert_eq!((owned.format, owned.hash.as_str()), (Format::Htpasswd, hash));
+        assert_eq!(owned.user.as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn accounts_without_hashes_are_skipped() {
+        assert!(skipped("root:*:19000:0:99999:7:::"));
+        assert!(skipped("daemon:!:19000:0:99999:7:::"));
+        assert!(skipped("locked:!$6$salt$hash:19000:0:99999:7:::"));
+        assert!(skipped("nopass::19000:0:99999:7:::"));
+        assert!(skipped("eve:x:1002:1002::/home/eve:/bin/sh"));
+        assert!(skipped("svc:*:1003:1003::/:/usr/sbin/nologin"));
+        assert!(skipped("Guest:501:NO PASSWORD*********************:NO PASSWORD*********************:::"));
+        assert!(skipped("jdoe:aes256-cts-hmac-sha1-96:0123456789abcdef"));
+    }
+
+    #[test]
+    fn blank_and_comment_lines_are_ignored() {
+        assert_eq!(parse_line("", 1), None);
+        assert_eq!(parse_line("   \r", 1), None);
+        assert_eq!(parse_line("# dumped 2024-01-01", 1), None);
+    }
+
+    #[test]
+    fn malformed_lines_are_rejected() {
+        assert!(malformed("garbage:line:with:four"));
+        assert!(malformed(&format!(":{}", MD5)));
+        assert!(malformed("frank:"));
+        assert!(malformed("a:b:c:d:e:f:g"));
+        assert!(malformed(&format!("x:500:{}:{}:::", LM, "z".repeat(32))));
+        assert!(malformed("root:$6$x:abc:0:99999:7:::"));
+    }
+
+    #[test]
+    fn rejected_lines_keep_their_numbers() {
+        let mut file = format!(
+            "# comment\n{}\ngarbage:line:with:four\nroot:*:19000:0:99999:7:::\r\nalice:{}\n",
+            MD5, MD5
+        )
+        .into_bytes();
+        // Line 6 isn't UTF-8
+        file.extend_from_slice(b"\xff\n");
+
+        let list = TargetList::parse(&file[..]).unwrap();
+        let lines: Vec<u64> = list.targets.iter().map(|target| target.line).collect();
+        assert_eq!(lines, [2, 5]);
+        let skipped: Vec<u64> = list.skipped.iter().map(|rejected| rejected.line).collect();
+        assert_eq!(skipped, [4]);
+        let malformed: Vec<u64> = list.malformed.iter().map(|rejected| rejected.line).collect();
+        assert_eq!(malformed, [3, 6]);
+    }
+}
@@ -0,0 +1,37 @@
+use std::io;
+use hash_forge::error::{Error, Result};
+use hash_forge::wordlist::{candidate, read_line};
+use hash_forge::create_verifier;
+use crate::{ScryptCommand, VerifyArgs};
+
+/// Checks `--password` (or the first stdin line) against `--hash`.
+pub fn run(args: &VerifyArgs) -> Result<bool> {
+    let target_hash = args.hash.trim();
+    if target_hash.is_empty() {
+        return Err(Error::Parse("Hash cannot be empty".into()));
+    }
+
+    let password = match &args.password {
+        Some(password) => password.as_bytes().to_vec(),
+        None => {
+            let mut line = Vec::new();
+            read_line(&mut io::stdin().lock(), &mut line)
+                .map_err(Error::io("Error reading stdin"))?;
+            candidate(&line, false).into_owned()
+        }
+    };
+
+    let transcoder = args.encoding.transcoder();
+    let password = transcoder
+        .apply(&password)
+        .ok_or_else(|| Error::Unsupported(format!("Password not convertible {}", transcoder)))?;
+
+    let verifier = create_verifier(&args.mode, target_hash, args.scrypt.as_ref().map(ScryptCommand::params))?;
+    let matched = verifier.verify(&password);
+    if matched {
+        println!("✅ Match");
+    } else {
+        println!("❌ No match");
+    }
+    Ok(matched)
+}
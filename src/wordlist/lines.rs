@@ -0,0 +1,155 @@
+use std::io::{self, BufRead, Read};
+use memmap2::Mmap;
+
+/// Line reader over an opened wordlist. Memory-mapped files hand out slices
+/// borrowed from the mapping and buffered streams reuse a single line
+/// buffer, so neither allocates per line. Terminators (`\n`, `\r\n`) are
+/// stripped.
+pub enum Lines {
+    Mapped { map: Mmap, pos: usize },
+    Buffered { reader: Box<dyn BufRead>, buf: Vec<u8>, pos: u64 },
+}
+
+impl Lines {
+    pub fn mapped(map: Mmap) -> Self {
+        Lines::Mapped { map, pos: 0 }
+    }
+
+    pub fn buffered(reader: Box<dyn BufRead>) -> Self {
+        Lines::Buffered { reader, buf: Vec::new(), pos: 0 }
+    }
+
+    /// Bytes consumed so far, counted on the decompressed stream.
+    pub fn position(&self) -> u64 {
+        match self {
+            Lines::Mapped { pos, .. } => *pos as u64,
+            Lines::Buffered { pos, .. } => *pos,
+        }
+    }
+
+    /// Moves past the first `offset` bytes, used to resume a session.
+    pub fn skip_to(&mut self, offset: u64) -> io::Result<()> {
+        match self {
+            Lines::Mapped { map, pos } => *pos = (offset as usize).min(map.len()),
+            Lines::Buffered { reader, pos, .. } => {
+                *pos += io::copy(&mut reader.as_mut().take(offset), &mut io::sink())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn next_line(&mut self) -> io::Result<Option<&[u8]>> {
+        match self {
+            Lines::Mapped { map, pos } => {
+                let rest = &map[*pos..];
+                if rest.is_empty() {
+                    return Ok(None);
+                }
+                let (line, consumed) = match memchr::memchr(b'\n', rest) {
+                    Some(end) => {
+                        let line = &rest[..end];
+                        (line.strip_suffix(b"\r").unwrap_or(line), end + 1)
+                    }
+                    // No terminator on the last line, so a `\r` there is data
+                    None => (rest, rest.len()),
+                };
+                *pos += consumed;
+                Ok(Some(line))
+            }
+            Lines::Buffered { reader, buf, pos } => match read_line(reader.as_mut(), buf)? {
+                0 => Ok(None),
+                consumed => {
+                    *pos += consumed as u64;
+                    Ok(Some(buf))
+                }
+            },
+        }
+    }
+}
+
+/// Reads the next raw line into `buf`, stripping only the `\n` or `\r\n`
+/// terminator. Returns the bytes consumed, 0 at end of input.
+pub fn read_line(reader: &mut dyn BufRead, buf: &mut Vec<u8>) -> io::Result<usize> {
+    buf.clear();
+    let consumed = reader.read_until(b'\n', buf)?;
+    if consumed == 0 {
+        return Ok(0);
+    }
+    if buf.last() == Some(&b'\n') {
+        buf.pop();
+        if buf.last() == Some(&b'\r') {
+            buf.pop();
+        }
+    }
+    Ok(consumed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use memmap2::MmapMut;
+    use super::*;
+
+    const DATA: &[u8] = b"a\r\nb\rc\n\r\n\nmid\r\rdle\nlast\r";
+
+    fn mapped(data: &[u8]) -> Lines {
+        let mut map = MmapMut::map_anon(data.len().max(1)).unwrap();
+        map[..data.len()].copy_from_slice(data);
+        let map = map.make_read_only().unwrap();
+        let mut lines = Lines::mapped(map);
+        if data.is_empty() {
+            // An anonymous map can't be empty; skip its one byte
+            lines.skip_to(1).unwrap();
+        }
+        lines
+    }
+
+    fn buffered(data: &[u8]) -> Lines {
+        Lines::buffered(Box::new(Cursor::new(data.to_vec())))
+    }
+
+    /// Every line with the position after it.
+    fn read_all(mut lines: Lines) -> Vec<(Vec<u8>, u64)> {
+        let mut all = Vec::new();
+        while let Some(line) = lines.next_line().unwrap() {
+            let line = line.to_vec();
+            all.push((line, lines.position()));
+        }
+        all
+    }
+
+    #[test]
+    fn only_line_terminators_are_stripped() {
+        let expected: Vec<(Vec<u8>, u64)> = [
+            (&b"a"[..], 3),
+            (b"b\rc", 7),
+            (b"", 9),
+            (b"", 10),
+            (b"mid\r\rdle", 19),
+            (b"last\r", 24),
+        ]
+        .iter()
+        .map(|(line, pos)| (line.to_vec(), *pos))
+        .collect();
+        assert_eq!(read_all(mapped(DATA)), expected);
+        assert_eq!(read_all(buffered(DATA)), expected);
+    }
+
+    #[test]
+    fn empty_input_has_no_lines() {
+        assert!(read_all(mapped(b"")).is_empty());
+        assert!(read_all(buffered(b"")).is_empty());
+        assert_eq!(read_all(buffered(b"\n")), [(Vec::new(), 1)]);
+    }
+
+    #[test]
+    fn skip_to_resumes_at_a_line_start() {
+        for mut lines in [mapped(DATA), buffered(DATA)] {
+            lines.skip_to(7).unwrap();
+            assert_eq!(lines.position(), 7);
+            assert_eq!(lines.next_line().unwrap(), Some(&b""[..]));
+            assert_eq!(lines.next_line().unwrap(), Some(&b""[..]));
+            assert_eq!(lines.next_line().unwrap(), Some(&b"mid\r\rdle"[..]));
+        }
+    }
+}
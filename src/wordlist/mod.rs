@@ -0,0 +1,355 @@
+use std::{
+    borrow::Cow,
+    fmt, fs,
+    io::{self, BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+};
+use bzip2::bufread::MultiBzDecoder;
+use flate2::bufread::MultiGzDecoder;
+use memmap2::Mmap;
+use xz2::bufread::XzDecoder;
+use zstd::stream::read::Decoder as ZstdDecoder;
+use crate::error::{Error, Result};
+
+mod lines;
+pub use lines::{read_line, Lines};
+
+/// Where candidates are read from: a file on disk or standard input (`-`).
+pub enum Source {
+    Stdin,
+    File(PathBuf),
+}
+
+impl Source {
+    /// Opens the source, transparently decompressing it if its magic bytes
+    /// identify a supported compression format. Plain regular files are
+    /// memory-mapped, everything else is read through a buffer.
+    pub fn open(&self) -> Result<(Lines, Compression)> {
+        let raw: Box<dyn BufRead> = match self {
+            Source::Stdin => Box::new(io::stdin().lock()),
+            Source::File(path) => {
+                let file = fs::File::open(path)
+                    .map_err(Error::io(format!("Error reading file {}", path.display())))?;
+                if file.metadata().is_ok_and(|meta| meta.is_file()) {
+                    // SAFETY: wordlists are not expected to change while they are read;
+                    // if the map fails (e.g. unsupported filesystem) fall back to reads
+                    if let Ok(map) = unsafe { Mmap::map(&file) } {
+                        return match Compression::detect(&map) {
+                            Compression::None => Ok((Lines::mapped(map), Compression::None)),
+                            compression => self.decompress(Box::new(io::Cursor::new(map)), compression),
+                        };
+                    }
+                }
+                Box::new(BufReader::new(file))
+            }
+        };
+        self.decompress_detected(raw)
+    }
+
+    /// Size in bytes of an uncompressed regular file, for progress reporting.
+    /// `None` for stdin and compressed files, whose stream length is unknown.
+    pub fn plain_size(&self) -> Option<u64> {
+        let Source::File(path) = self else {
+            return None;
+        };
+        let file = fs::File::open(path).ok()?;
+        let meta = file.metadata().ok()?;
+        if !meta.is_file() {
+            return None;
+        }
+        let mut magic = Vec::with_capacity(6);
+        file.take(6).read_to_end(&mut magic).ok()?;
+        (Compression::detect(&magic) == Compression::None).then_some(meta.len())
+    }
+
+    fn decompress_detected(&self, mut raw: Box<dyn BufRead>) -> Result<(Lines, Compression)> {
+        let compression = Compression::detect(
+            raw.fill_buf()
+                .map_err(Error::io(format!("Error reading {}", self)))?,
+        );
+        self.decompress(raw, compression)
+    }
+
+    fn decompress(
+        &self,
+        raw: Box<dyn BufRead>,
+        compression: Compression,
+    ) -> Result<(Lines, Compression)> {
+        let reader: Box<dyn BufRead> = match compression {
+            Compression::None => raw,
+            Compression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(raw))),
+            Compression::Bzip2 => Box::new(BufReader::new(MultiBzDecoder::new(raw))),
+            Compression::Xz => Box::new(BufReader::new(XzDecoder::new_multi_decoder(raw))),
+            Compression::Zstd => Box::new(BufReader::new(
+                ZstdDecoder::with_buffer(raw)
+                    .map_err(Error::io(format!("Error reading {}", self)))?,
+            )),
+        };
+        Ok((Lines::buffered(reader), compression))
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Stdin => write!(f, "<stdin>"),
+            Source::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Compression formats recognised from a wordlist's leading magic bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    fn detect(magic: &[u8]) -> Self {
+        if magic.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if magic.starts_with(b"BZh") {
+            Compression::Bzip2
+        } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Compression::Xz
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Compression::None => "plain",
+            Compression::Gzip => "gzip",
+            Compression::Bzip2 => "bzip2",
+            Compression::Xz => "xz",
+            Compression::Zstd => "zstd",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Expands `--file` arguments into the ordered list of sources to read.
+/// `-` is stdin, directories are walked recursively in name order.
+pub fn expand_sources(paths: &[PathBuf]) -> Result<Vec<Source>> {
+    let mut sources = Vec::new();
+    let mut seen_stdin = false;
+
+    for path in paths {
+        if path.as_os_str() == "-" {
+            if seen_stdin {
+                return Err(Error::Param("stdin (-) can only be given once".into()));
+            }
+            seen_stdin = true;
+            sources.push(Source::Stdin);
+        } else if fs::metadata(path)
+            .map_err(Error::io(format!("Error reading {}", path.display())))?
+            .is_dir()
+        {
+            walk_dir(path, &mut sources)?;
+        } else {
+            sources.push(Source::File(path.clone()));
+        }
+    }
+    Ok(sources)
+}
+
+fn walk_dir(dir: &Path, sources: &mut Vec<Source>) -> Result<()> {
+    let mut entries = fs::read_dir(dir)
+        .and_then(|entries| entries.collect::<io::Result<Vec<_>>>())
+        .map_err(Error::io(format!("Error reading directory {}", dir.display())))?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        let file_type = entry
+            .file_type()
+            .map_err(Error::io(format!("Error reading {}", path.display())))?;
+        if file_type.is_dir() {
+            walk_dir(&path, sources)?;
+        } else if path.is_file() {
+            // Symlinked files are followed, symlinked directories are not
+            sources.push(Source::File(path));
+        }
+    }
+    Ok(())
+}
+
+/// Total number of candidates across `sources`, the range `--skip` and
+/// `--limit` index into.
+pub fn keyspace(sources: &[Source]) -> Result<u64> {
+    let mut total = 0;
+    for source in sources {
+        let (mut lines, _) = source.open()?;
+        while lines
+            .next_line()
+            .map_err(Error::io(format!("Error reading {}", source)))?
+            .is_some()
+        {
+            total += 1;
+        }
+    }
+    Ok(total)
+}
+
+/// Every candidate of `sources` in memory, for attacks that go through a
+/// wordlist once for each line of another.
+pub fn read_words(sources: &[Source], trim: bool) -> Result<Vec<Vec<u8>>> {
+    let mut words = Vec::new();
+    for source in sources {
+        let (mut lines, _) = source.open()?;
+        while let Some(line) = lines
+            .next_line()
+            .map_err(Error::io(format!("Error reading {}", source)))?
+        {
+            words.push(candidate(line, trim).into_owned());
+        }
+    }
+    Ok(words)
+}
+
+/// Turns a wordlist line into the candidate bytes: optionally trims ASCII
+/// whitespace and decodes hashcat-style `$HEX[...]` entries.
+pub fn candidate(line: &[u8], trim: bool) -> Cow<'_, [u8]> {
+    let line = if trim { line.trim_ascii() } else { line };
+    match line
+        .strip_prefix(b"$HEX[")
+        .and_then(|rest| rest.strip_suffix(b"]"))
+        .and_then(|hex_digits| hex::decode(hex_digits).ok())
+    {
+        Some(decoded) => Cow::Owned(decoded),
+        None => Cow::Borrowed(line),
+    }
+}
+
+/// Printable form of a candidate: the text itself when it is clean UTF-8,
+/// `$HEX[...]` otherwise so the exact bytes survive copy and paste.
+pub fn display_candidate(word: &[u8]) -> Cow<'_, str> {
+    match std::str::from_utf8(word) {
+        Ok(text) if !text.chars().any(char::is_control) && !text.starts_with("$HEX[") => {
+            Cow::Borrowed(text)
+        }
+        _ => Cow::Owned(format!("$HEX[{}]", hex::encode(word))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+    use super::*;
+
+    const WORDS: &[u8] = b"alpha\r\nbeta\n\n$HEX[41]\nlast";
+
+    fn compress(compression: Compression, data: &[u8]) -> Vec<u8> {
+        match compression {
+            Compression::None => data.to_vec(),
+            Compression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Bzip2 => {
+                let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Zstd => zstd::encode_all(data, 0).unwrap(),
+        }
+    }
+
+    /// Every line of `data`, read the way an opened stream is.
+    fn lines(data: Vec<u8>) -> (Vec<Vec<u8>>, Compression) {
+        let (mut lines, compression) = Source::Stdin.decompress_detected(Box::new(Cursor::new(data))).unwrap();
+        let mut all = Vec::new();
+        while let Some(line) = lines.next_line().unwrap() {
+            all.push(line.to_vec());
+        }
+        (all, compression)
+    }
+
+    const ALL: [Compression; 5] =
+        [Compression::None, Compression::Gzip, Compression::Bzip2, Compression::Xz, Compression::Zstd];
+
+    #[test]
+    fn compression_is_detected_from_magic_bytes() {
+        for compression in ALL {
+            assert_eq!(Compression::detect(&compress(compression, WORDS)), compression);
+        }
+        for plain in [&b""[..], b"\x1f", b"BZ", b"gzip.txt\n", b"\xfd7zX"] {
+            assert_eq!(Compression::detect(plain), Compression::None, "{:?}", plain);
+        }
+    }
+
+    #[test]
+    fn compressed_wordlists_read_like_plain_ones() {
+        let (plain, _) = lines(WORDS.to_vec());
+        assert_eq!(plain, [&b"alpha"[..], b"beta", b"", b"$HEX[41]", b"last"]);
+        for compression in ALL {
+            assert_eq!(lines(compress(compression, WORDS)), (plain.clone(), compression));
+        }
+    }
+
+    #[test]
+    fn concatenated_streams_are_read_to_the_end() {
+        for compression in [Compression::Gzip, Compression::Bzip2, Compression::Xz, Compression::Zstd] {
+            let data = [compress(compression, b"one\n"), compress(compression, b"two\n")].concat();
+            assert_eq!(lines(data).0, [b"one", b"two"], "{}", compression);
+        }
+    }
+
+    #[test]
+    fn hex_lines_are_decoded() {
+        assert_eq!(candidate(b"$HEX[70617373]", false), &b"pass"[..]);
+        assert_eq!(candidate(b"$HEX[5C0aFF]", false), &b"\\\n\xff"[..]);
+        assert_eq!(candidate(b"$HEX[]", false), &b""[..]);
+        // Anything malformed is taken literally
+        for line in [&b"$HEX[7g]"[..], b"$HEX[414]", b"$HEX[41", b"$hex[41]", b"x$HEX[41]", b"$HEX[41] "] {
+            assert_eq!(candidate(line, false), line);
+        }
+    }
+
+    #[test]
+    fn trim_strips_surrounding_whitespace_only() {
+        assert_eq!(candidate(b" \tpass word\t ", true), &b"pass word"[..]);
+        assert_eq!(candidate(b" \tpass word\t ", false), &b" \tpass word\t "[..]);
+        assert_eq!(candidate(b"   ", true), &b""[..]);
+        // Trimming comes first, so padded $HEX[] lines decode too
+        assert_eq!(candidate(b" $HEX[41] ", true), &b"A"[..]);
+    }
+
+    #[test]
+    fn candidates_display_as_hex_unless_clean_text() {
+        assert_eq!(display_candidate(b"pass word"), "pass word");
+        assert_eq!(display_candidate("pässword".as_bytes()), "pässword");
+        assert_eq!(display_candidate(b"tab\there"), "$HEX[7461620968657265]");
+        assert_eq!(display_candidate(b"\xff"), "$HEX[ff]");
+        assert_eq!(display_candidate(b"$HEX[41]"), "$HEX[244845585b34315d]");
+    }
+
+    #[test]
+    fn corrupt_compressed_data_is_an_error() {
+        let mut data = compress(Compression::Gzip, &WORDS.repeat(100));
+        data.truncate(data.len() / 2);
+        let (mut lines, _) = Source::Stdin.decompress_detected(Box::new(Cursor::new(data))).unwrap();
+        let result = loop {
+            match lines.next_line() {
+                Ok(Some(_)) => continue,
+                other => break other.map(|line| line.is_some()),
+            }
+        };
+        assert!(result.is_err());
+    }
+}